@@ -0,0 +1,10 @@
+#![no_main]
+use levenshtein_automata::DFA;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(dfa) = DFA::from_bytes(data) {
+        // A deserialized `DFA` is valid, so evaluating it must not panic either.
+        let _ = dfa.eval(data);
+    }
+});
@@ -0,0 +1,36 @@
+#![no_main]
+use levenshtein_automata::{Distance, DFA};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 3 {
+        return;
+    }
+    let num_states = (data[0] % 8) as usize;
+    let initial_state = u32::from(data[1] % 10);
+    let mut bytes = data[2..].iter().cycle();
+    let distances: Vec<Distance> = (0..num_states)
+        .map(|_| {
+            let d = *bytes.next().unwrap();
+            if d & 128 == 0 {
+                Distance::Exact(d & 3)
+            } else {
+                Distance::AtLeast(d & 3)
+            }
+        })
+        .collect();
+    let num_transition_rows = num_states + usize::from(data.len() % 3 == 0);
+    let transitions: Vec<[u32; 256]> = (0..num_transition_rows)
+        .map(|_| {
+            let mut row = [0u32; 256];
+            for dest in row.iter_mut() {
+                *dest = u32::from(*bytes.next().unwrap() % 10);
+            }
+            row
+        })
+        .collect();
+    let dfa = DFA::from_raw_parts(transitions, distances, initial_state);
+    if dfa.validate().is_ok() {
+        let _ = dfa.eval(data);
+    }
+});
@@ -0,0 +1,64 @@
+#ifndef LEVENSHTEIN_AUTOMATA_H
+#define LEVENSHTEIN_AUTOMATA_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Bit set in the result of `lev_dfa_eval` when the distance is not exact.
+ */
+#define LEV_DFA_NOT_EXACT 128
+
+typedef struct DFA DFA;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Builds the DFA computing the Levenshtein distance to `query`.
+ *
+ * Returns a null pointer if `query` is null or is not valid UTF-8,
+ * or if `max_distance` is not in `1..=4`.
+ * The DFA must be released with `lev_dfa_free`.
+ *
+ * # Safety
+ *
+ * `query` must be null or point to a NUL-terminated string.
+ */
+DFA *lev_dfa_build(const char *query, uint8_t max_distance, bool transposition);
+
+/**
+ * Returns the distance between the query of the `dfa` and the `len` bytes of `text`.
+ *
+ * If the distance is not exact, i.e. larger than the max distance of the DFA,
+ * the `LEV_DFA_NOT_EXACT` bit is set. See `lev_dfa_is_exact`.
+ *
+ * # Safety
+ *
+ * `dfa` must have been returned by `lev_dfa_build` and not freed,
+ * and `text` must point to `len` readable bytes.
+ */
+uint8_t lev_dfa_eval(const DFA *dfa, const char *text, uintptr_t len);
+
+/**
+ * Returns true iff the result of `lev_dfa_eval` is an exact distance.
+ */
+bool lev_dfa_is_exact(uint8_t result);
+
+/**
+ * Releases a DFA returned by `lev_dfa_build`. Does nothing if `dfa` is null.
+ *
+ * # Safety
+ *
+ * `dfa` must be null or have been returned by `lev_dfa_build`, and not freed yet.
+ */
+void lev_dfa_free(DFA *dfa);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* LEVENSHTEIN_AUTOMATA_H */
@@ -0,0 +1,17 @@
+from levenshtein_automata import LevenshteinAutomatonBuilder
+
+
+def test_build_dfa():
+    builder = LevenshteinAutomatonBuilder(2, True)
+    dfa = builder.build_dfa("Levenshtein")
+    assert dfa.eval("Levenshtein") == 0
+    assert dfa.eval("Levenshtain") == 1
+    assert dfa.eval("Lveenshtein") == 1
+    assert dfa.eval("Hello") == 3
+
+
+def test_build_prefix_dfa():
+    builder = LevenshteinAutomatonBuilder(1, False)
+    dfa = builder.build_prefix_dfa("Leven")
+    assert dfa.eval("Levenshtein") == 0
+    assert dfa.eval("Lefenshtein") == 1
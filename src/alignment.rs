@@ -0,0 +1,127 @@
+use super::Distance;
+
+/// Kind of an [AlignOp](./struct.AlignOp.html).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AlignKind {
+    /// The char of the query and the char of the text are equal.
+    Match,
+    /// The char of the query is replaced by the char of the text.
+    Substitute,
+    /// The char of the text is inserted before the char of the query.
+    Insert,
+    /// The char of the query is deleted.
+    Delete,
+    /// The two chars of the query starting at `query_pos` are swapped,
+    /// giving the two chars of the text starting at `text_pos`.
+    ///
+    /// Only used if the `DFA` assigns a distance of 1 to transpositions.
+    Transpose,
+}
+
+/// One step of an [Alignment](./struct.Alignment.html).
+///
+/// Positions are expressed in chars.
+/// For an insertion, `query_pos` is the position of the query at
+/// which the char of the text is inserted. For a deletion, `text_pos`
+/// is the position of the text at which the char of the query is deleted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AlignOp {
+    pub query_pos: usize,
+    pub text_pos: usize,
+    pub kind: AlignKind,
+}
+
+/// Optimal alignment of a text with the query of a [DFA](./struct.DFA.html).
+///
+/// See [DFA::align(...)](./struct.DFA.html#method.align).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Alignment {
+    /// The distance, as returned by [DFA::eval(...)](./struct.DFA.html#method.eval).
+    pub distance: Distance,
+    /// The sequence of operations transforming the query into the text.
+    pub ops: Vec<AlignOp>,
+}
+
+// The query of a `DFA`, and the edit model the `DFA` was built with.
+#[derive(Clone)]
+pub(crate) struct AlignmentQuery {
+    pub chars: Vec<char>,
+    pub prefix: bool,
+    pub transposition: bool,
+}
+
+impl AlignmentQuery {
+    /// Computes an alignment of minimal distance between the query and the `text`.
+    ///
+    /// Edits are substitutions, insertions, deletions and, if `transposition` is true,
+    /// transpositions of two adjacent chars. As in the Levenshtein automaton, a char
+    /// cannot be edited again after being transposed.
+    pub fn align(&self, text: &[char]) -> Vec<AlignOp> {
+        let query = &self.chars[..];
+        let num_cols = text.len() + 1;
+        let mut costs = vec![0u32; (query.len() + 1) * num_cols];
+        for (j, cost) in costs[..num_cols].iter_mut().enumerate() {
+            *cost = j as u32;
+        }
+        for i in 1..=query.len() {
+            costs[i * num_cols] = i as u32;
+            for j in 1..num_cols {
+                let substitution_cost = u32::from(query[i - 1] != text[j - 1]);
+                let mut cost = (costs[(i - 1) * num_cols + j - 1] + substitution_cost)
+                    .min(costs[(i - 1) * num_cols + j] + 1)
+                    .min(costs[i * num_cols + j - 1] + 1);
+                if self.is_transposition(text, i, j) {
+                    cost = cost.min(costs[(i - 2) * num_cols + j - 2] + 1);
+                }
+                costs[i * num_cols + j] = cost;
+            }
+        }
+
+        let mut i = query.len();
+        let mut j = text.len();
+        let mut ops = Vec::new();
+        while i > 0 || j > 0 {
+            let cost = costs[i * num_cols + j];
+            let (kind, di, dj) = if i > 0
+                && j > 0
+                && costs[(i - 1) * num_cols + j - 1] + u32::from(query[i - 1] != text[j - 1])
+                    == cost
+            {
+                if query[i - 1] == text[j - 1] {
+                    (AlignKind::Match, 1, 1)
+                } else {
+                    (AlignKind::Substitute, 1, 1)
+                }
+            } else if self.is_transposition(text, i, j)
+                && costs[(i - 2) * num_cols + j - 2] + 1 == cost
+            {
+                (AlignKind::Transpose, 2, 2)
+            } else if i > 0 && costs[(i - 1) * num_cols + j] + 1 == cost {
+                (AlignKind::Delete, 1, 0)
+            } else {
+                (AlignKind::Insert, 0, 1)
+            };
+            i -= di;
+            j -= dj;
+            ops.push(AlignOp {
+                query_pos: i,
+                text_pos: j,
+                kind,
+            });
+        }
+        ops.reverse();
+        ops
+    }
+
+    // Returns true iff the chars of the query ending at `i` and the chars
+    // of the `text` ending at `j` are two distinct chars, swapped.
+    fn is_transposition(&self, text: &[char], i: usize, j: usize) -> bool {
+        let query = &self.chars[..];
+        self.transposition
+            && i >= 2
+            && j >= 2
+            && query[i - 1] != query[i - 2]
+            && query[i - 1] == text[j - 2]
+            && query[i - 2] == text[j - 1]
+    }
+}
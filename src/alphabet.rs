@@ -1,31 +1,99 @@
+use std::fmt;
 use std::slice;
 
+/// Bitset of the positions at which a char appears in the query.
+///
+/// The bit `i % 32` of the `i / 32`-th bucket is set iff the char appears
+/// at the position `i` of the query.
 #[derive(Clone)]
 pub struct FullCharacteristicVector(Vec<u32>);
 
+impl fmt::Debug for FullCharacteristicVector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FullCharacteristicVector([")?;
+        for (i, bucket) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:#010x}", bucket)?;
+        }
+        write!(f, "])")
+    }
+}
+
 impl FullCharacteristicVector {
+    /// Returns the characteristic vector of the char for the window of the query
+    /// starting at `offset`, as expected by
+    /// [ParametricDFA::transition(...)](./struct.ParametricDFA.html#method.transition).
+    ///
+    /// The bit `i` of the result is set iff the char appears at the position
+    /// `offset + i` of the query. `mask` selects the bits within the window,
+    /// i.e. `(1 << diameter) - 1` where `diameter` is the number of positions of
+    /// the query a state of the parametric DFA can look at. Positions past the end
+    /// of the query are never set.
     pub fn shift_and_mask(&self, offset: usize, mask: u32) -> u32 {
         let bucket_id = offset / 32;
         let align = offset - bucket_id * 32;
         if align == 0 {
             self.0[bucket_id] & mask
         } else {
-            let left = (self.0[bucket_id] >> align) as u32;
+            let left = self.0[bucket_id] >> align;
             let right = self.0[bucket_id + 1] << (32 - align) as u32;
             (left | right) & mask
         }
     }
+
+    /// Returns true iff the char appears at the position `pos` of the query.
+    pub fn bit_at(&self, pos: usize) -> bool {
+        let bucket = self.0.get(pos / 32).cloned().unwrap_or(0u32);
+        (bucket >> (pos % 32)) & 1u32 == 1u32
+    }
+
+    /// Returns the positions of the query at which the char appears.
+    pub fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.0.len() * 32).filter(move |&pos| self.bit_at(pos))
+    }
 }
 
+/// The distinct chars of a query, associated to their
+/// [FullCharacteristicVector](./struct.FullCharacteristicVector.html).
 pub struct Alphabet {
     charset: Vec<(char, FullCharacteristicVector)>,
 }
 
+impl fmt::Debug for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.charset.iter().map(|(c, chi)| (c, chi)))
+            .finish()
+    }
+}
+
 impl Alphabet {
-    pub fn iter(&self) -> slice::Iter<(char, FullCharacteristicVector)> {
+    pub fn iter(&self) -> slice::Iter<'_, (char, FullCharacteristicVector)> {
         self.charset.iter()
     }
 
+    /// Returns the number of distinct chars in the alphabet.
+    pub fn len(&self) -> usize {
+        self.charset.len()
+    }
+
+    /// Returns true iff `c` is one of the chars of the query.
+    ///
+    /// The transitions of the other chars all lead to the default successor state.
+    pub fn contains(&self, c: char) -> bool {
+        self.charset
+            .binary_search_by_key(&c, |&(chr, _)| chr)
+            .is_ok()
+    }
+
+    /// Returns true iff the alphabet does not contain any char.
+    pub fn is_empty(&self) -> bool {
+        self.charset.is_empty()
+    }
+
+    /// Builds the alphabet of a query, given its chars.
     pub fn for_query_chars(query_chars: &[char]) -> Alphabet {
         let mut charset = Vec::from(query_chars);
         charset.sort();
@@ -51,7 +119,7 @@ impl Alphabet {
                 (c, FullCharacteristicVector(bits))
             })
             .collect();
-        Alphabet { charset: charset }
+        Alphabet { charset }
     }
 }
 
@@ -63,30 +131,48 @@ mod tests {
     fn test_alphabet() {
         let chars: Vec<char> = "happy".chars().collect();
         let alphabet = Alphabet::for_query_chars(&chars);
+        assert_eq!(alphabet.len(), 4);
+        assert!(!alphabet.is_empty());
+        assert!(Alphabet::for_query_chars(&[]).is_empty());
+        assert!(alphabet.contains('p'));
+        assert!(!alphabet.contains('b'));
         let mut it = alphabet.iter();
 
         {
-            let &(ref c, ref chi) = it.next().unwrap();
+            let (c, chi) = it.next().unwrap();
             assert_eq!(*c, 'a');
             assert_eq!(chi.0[0], 2u32);
         }
         {
-            let &(ref c, ref chi) = it.next().unwrap();
+            let (c, chi) = it.next().unwrap();
             assert_eq!(*c, 'h');
             assert_eq!(chi.0[0], 1u32);
         }
         {
-            let &(ref c, ref chi) = it.next().unwrap();
+            let (c, chi) = it.next().unwrap();
             assert_eq!(*c, 'p');
             assert_eq!(chi.0[0], 4u32 + 8u32);
         }
         {
-            let &(ref c, ref chi) = it.next().unwrap();
+            let (c, chi) = it.next().unwrap();
             assert_eq!(*c, 'y');
             assert_eq!(chi.0[0], 16u32);
         }
     }
 
+    #[test]
+    fn test_debug() {
+        let chars: Vec<char> = "happy".chars().collect();
+        let alphabet = Alphabet::for_query_chars(&chars);
+        assert_eq!(
+            format!("{:?}", alphabet),
+            "{'a': FullCharacteristicVector([0x00000002, 0x00000000]), \
+             'h': FullCharacteristicVector([0x00000001, 0x00000000]), \
+             'p': FullCharacteristicVector([0x0000000c, 0x00000000]), \
+             'y': FullCharacteristicVector([0x00000010, 0x00000000])}"
+        );
+    }
+
     #[test]
     fn test_full_characteristic() {
         assert_eq!(
@@ -100,13 +186,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bit_at() {
+        let chars: Vec<char> = "happy".chars().collect();
+        let alphabet = Alphabet::for_query_chars(&chars);
+        let (c, chi) = &alphabet.iter().nth(2).unwrap();
+        assert_eq!(*c, 'p');
+        let bits: Vec<bool> = (0..6).map(|pos| chi.bit_at(pos)).collect();
+        assert_eq!(bits, vec![false, false, true, true, false, false]);
+        assert!(!chi.bit_at(1000));
+    }
+
+    #[test]
+    fn test_positions() {
+        let chars: Vec<char> = "abracadabra-abracadabra-abracadabra".chars().collect();
+        let alphabet = Alphabet::for_query_chars(&chars);
+        let (c, chi) = &alphabet.iter().nth(2).unwrap();
+        assert_eq!(*c, 'b');
+        let positions: Vec<usize> = chi.positions().collect();
+        assert_eq!(positions, vec![1, 8, 13, 20, 25, 32]);
+    }
+
     #[test]
     fn test_long_characteristic() {
         let query_chars: Vec<char> = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaabcabewa".chars().collect();
         let alphabet = Alphabet::for_query_chars(&query_chars[..]);
         let mut alphabet_it = alphabet.iter();
         {
-            let &(ref c, ref chi) = alphabet_it.next().unwrap();
+            let (c, chi) = alphabet_it.next().unwrap();
             assert_eq!(*c, 'a');
             assert_eq!(chi.shift_and_mask(0, 7), 7);
             assert_eq!(chi.shift_and_mask(28, 7), 3);
@@ -114,7 +221,7 @@ mod tests {
             assert_eq!(chi.shift_and_mask(28, 4095), 1 + 2 + 16 + 256);
         }
         {
-            let &(ref c, ref chi) = alphabet_it.next().unwrap();
+            let (c, chi) = alphabet_it.next().unwrap();
             assert_eq!(*c, 'b');
             assert_eq!(chi.shift_and_mask(0, 7), 0);
             assert_eq!(chi.shift_and_mask(28, 15), 4);
@@ -115,3 +115,86 @@ fn bench_build_parametricdfa_damerau_perf_2_profile(b: &mut Bencher) {
         let _dfa = parametric_dfa.build_dfa("Levenshtein", false);
     });
 }
+
+fn bench_eval(b: &mut Bencher, max_distance: u8, query: &str, text: &str) {
+    let nfa = LevenshteinNFA::levenshtein(max_distance, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa(query, false);
+    b.iter(|| dfa.eval(text));
+}
+
+fn alphabet_string(len: usize) -> String {
+    "abcdefghijklmnopqrstuvwxyz"
+        .chars()
+        .cycle()
+        .take(len)
+        .collect()
+}
+
+#[bench]
+fn bench_eval_10_chars_distance1(b: &mut Bencher) {
+    let query = alphabet_string(10);
+    bench_eval(b, 1, &query, &query);
+}
+
+#[bench]
+fn bench_eval_100_chars_distance2(b: &mut Bencher) {
+    let query = alphabet_string(100);
+    bench_eval(b, 2, &query, &query);
+}
+
+#[bench]
+fn bench_eval_1000_chars_distance2(b: &mut Bencher) {
+    let query = alphabet_string(1000);
+    bench_eval(b, 2, &query, &query);
+}
+
+#[bench]
+fn bench_eval_1kb_distance1(b: &mut Bencher) {
+    let query = alphabet_string(1024);
+    bench_eval(b, 1, &query, &query);
+}
+
+#[bench]
+fn bench_eval_sink_distance2(b: &mut Bencher) {
+    let query = alphabet_string(1000);
+    let text = "X".repeat(1000);
+    bench_eval(b, 2, &query, &text);
+}
+
+#[bench]
+fn bench_build_parametricdfa_perf_4(b: &mut Bencher) {
+    let nfa = LevenshteinNFA::levenshtein(4, false);
+    b.iter(|| ParametricDFA::from_nfa(&nfa));
+}
+
+// `d=5` is the practical maximum: the parametric DFA has 9714 states
+// (against 1354 for `d=4`), and building it takes a few seconds
+// in release mode. Ignored so that `cargo test` does not run it,
+// use `cargo bench -- --ignored perf_5` to run it.
+#[bench]
+#[ignore]
+fn bench_build_parametricdfa_perf_5(b: &mut Bencher) {
+    let nfa = LevenshteinNFA::levenshtein(5, false);
+    b.iter(|| {
+        let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+        assert_eq!(parametric_dfa.num_states(), 9714);
+    });
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn bench_build_parametricdfa_parallel_perf_3(b: &mut Bencher) {
+    let nfa = LevenshteinNFA::levenshtein(3, false);
+    b.iter(|| {
+        let parametric_dfa = ParametricDFA::from_nfa_parallel(&nfa);
+        assert_eq!(parametric_dfa.num_states(), 197);
+    });
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn bench_build_parametricdfa_parallel_perf_4(b: &mut Bencher) {
+    let nfa = LevenshteinNFA::levenshtein(4, false);
+    b.iter(|| ParametricDFA::from_nfa_parallel(&nfa));
+}
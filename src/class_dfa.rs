@@ -0,0 +1,129 @@
+use super::{Distance, DFA, SINK_STATE};
+use std::error::Error;
+use std::fmt;
+
+/// Error returned when two bytes of the same class lead to different states
+/// in a [DFA](./struct.DFA.html).
+///
+/// See [DFA::remap_to_byte_classes(...)](./struct.DFA.html#method.remap_to_byte_classes).
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct ByteClassError {
+    /// The state from which the bytes lead to different states.
+    pub state: u32,
+    /// A byte that does not lead to the same state as the other bytes of its class.
+    pub byte: u8,
+}
+
+impl fmt::Display for ByteClassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Byte {} does not lead to the same state as the other bytes of its class from state {}",
+            self.byte, self.state
+        )
+    }
+}
+
+impl Error for ByteClassError {}
+
+/// A [DFA](./struct.DFA.html) whose transitions are indexed by classes of bytes
+/// rather than by bytes.
+///
+/// Bytes of the same class must lead to the same state, whatever the state
+/// they are consumed from. Since a Levenshtein DFA only distinguishes the bytes
+/// appearing in the UTF-8 encoding of its query, there are few classes.
+/// For instance, the bytes are split into 10 classes for the query `"abcdef"`,
+/// which makes the transition table about 25 times smaller.
+///
+/// See [DFA::remap_to_byte_classes(...)](./struct.DFA.html#method.remap_to_byte_classes)
+/// and [DFA::byte_equivalence_classes(...)](./struct.DFA.html#method.byte_equivalence_classes).
+#[derive(Clone)]
+pub struct ClassDFA {
+    classes: [u8; 256],
+    transitions: Vec<Vec<u32>>,
+    distances: Vec<Distance>,
+    initial_state: u32,
+}
+
+impl ClassDFA {
+    pub(crate) fn from_dfa(dfa: &DFA, classes: &[u8; 256]) -> Result<ClassDFA, ByteClassError> {
+        let num_classes = classes.iter().cloned().max().unwrap_or(0u8) as usize + 1;
+        let mut transitions = Vec::with_capacity(dfa.num_states());
+        for state in 0..dfa.num_states() as u32 {
+            let mut row: Vec<Option<u32>> = vec![None; num_classes];
+            for (b, &class) in classes.iter().enumerate() {
+                let dest = dfa.transition(state, b as u8);
+                match row[class as usize] {
+                    None => row[class as usize] = Some(dest),
+                    Some(class_dest) if class_dest == dest => {}
+                    Some(_) => {
+                        return Err(ByteClassError {
+                            state,
+                            byte: b as u8,
+                        });
+                    }
+                }
+            }
+            // Classes without any byte are never looked up.
+            transitions.push(
+                row.into_iter()
+                    .map(|dest| dest.unwrap_or(SINK_STATE))
+                    .collect(),
+            );
+        }
+        let distances = dfa.states_iter().map(|(_, distance)| distance).collect();
+        Ok(ClassDFA {
+            classes: *classes,
+            transitions,
+            distances,
+            initial_state: dfa.initial_state(),
+        })
+    }
+
+    /// Returns the initial state
+    pub fn initial_state(&self) -> u32 {
+        self.initial_state
+    }
+
+    /// Helper function that consumes all of the bytes
+    /// a sequence of bytes and returns the resulting
+    /// distance.
+    pub fn eval<B: AsRef<[u8]>>(&self, text: B) -> Distance {
+        let mut state = self.initial_state();
+        for &b in text.as_ref() {
+            state = self.transition(state, b);
+        }
+        self.distance(state)
+    }
+
+    /// Returns the Levenshtein distance associated to the
+    /// current state.
+    pub fn distance(&self, state_id: u32) -> Distance {
+        self.distances[state_id as usize]
+    }
+
+    /// Returns the number of states in the `ClassDFA`.
+    pub fn num_states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns the number of classes of bytes.
+    pub fn num_classes(&self) -> usize {
+        self.transitions.first().map(Vec::len).unwrap_or(0)
+    }
+
+    /// Returns the class of a given byte.
+    pub fn class(&self, b: u8) -> u8 {
+        self.classes[b as usize]
+    }
+
+    /// Returns the destination state reached after consuming a byte of a given class.
+    pub fn class_transition(&self, from_state_id: u32, class: u8) -> u32 {
+        self.transitions[from_state_id as usize][class as usize]
+    }
+
+    /// Returns the destination state reached after consuming a given byte.
+    pub fn transition(&self, from_state_id: u32, b: u8) -> u32 {
+        self.class_transition(from_state_id, self.class(b))
+    }
+}
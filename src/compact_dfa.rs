@@ -0,0 +1,120 @@
+use super::{Distance, DFA};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+/// Error returned when the states of a [DFA](./struct.DFA.html) cannot be
+/// represented by the state id type of a [CompactDFA](./struct.CompactDFA.html).
+///
+/// It holds the number of states of the `DFA`.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct OverflowError(pub usize);
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The DFA has too many states ({}) for the state id type",
+            self.0
+        )
+    }
+}
+
+impl Error for OverflowError {}
+
+/// A [DFA](./struct.DFA.html) whose state ids are stored using
+/// the integer type `S` instead of `u32`.
+///
+/// Unlike [CompressedDFA](./struct.CompressedDFA.html), transitions remain
+/// a simple lookup. For a `DFA` with less than `65_536` states, a `CompactDFA<u16>`
+/// halves the size of the transition table.
+///
+/// See [DFA::to_u16_compact(...)](./struct.DFA.html#method.to_u16_compact)
+/// and [DFA::state_id_type(...)](./struct.DFA.html#method.state_id_type).
+#[derive(Clone)]
+pub struct CompactDFA<S> {
+    transitions: Vec<[S; 256]>,
+    distances: Vec<Distance>,
+    initial_state: S,
+}
+
+impl<S> CompactDFA<S>
+where
+    S: Copy + Default + Into<u32> + TryFrom<u32>,
+{
+    /// Converts a `DFA` into a `CompactDFA`.
+    ///
+    /// Returns an `OverflowError` if some of the states of the `DFA` cannot
+    /// be represented by `S`.
+    pub fn from_dfa(dfa: DFA) -> Result<CompactDFA<S>, OverflowError> {
+        let num_states = dfa.num_states();
+        let to_state_id = |state: u32| S::try_from(state).map_err(|_| OverflowError(num_states));
+        if num_states > 0 {
+            to_state_id(num_states as u32 - 1)?;
+        }
+        let (transitions, distances, initial_state) = dfa.into_raw_parts();
+        let transitions = transitions
+            .iter()
+            .map(|row| {
+                let mut compact_row = [S::default(); 256];
+                for (compact_dest, &dest) in compact_row.iter_mut().zip(row.iter()) {
+                    *compact_dest = to_state_id(dest)?;
+                }
+                Ok(compact_row)
+            })
+            .collect::<Result<Vec<_>, OverflowError>>()?;
+        Ok(CompactDFA {
+            transitions,
+            distances,
+            initial_state: to_state_id(initial_state)?,
+        })
+    }
+
+    /// Converts the `CompactDFA` back into a [DFA](./struct.DFA.html).
+    pub fn into_dfa(self) -> DFA {
+        let transitions = self
+            .transitions
+            .iter()
+            .map(|compact_row| {
+                let mut row = [0u32; 256];
+                for (dest, &compact_dest) in row.iter_mut().zip(compact_row.iter()) {
+                    *dest = compact_dest.into();
+                }
+                row
+            })
+            .collect();
+        DFA::from_raw_parts(transitions, self.distances, self.initial_state.into())
+    }
+
+    /// Returns the initial state
+    pub fn initial_state(&self) -> u32 {
+        self.initial_state.into()
+    }
+
+    /// Helper function that consumes all of the bytes
+    /// a sequence of bytes and returns the resulting
+    /// distance.
+    pub fn eval<B: AsRef<[u8]>>(&self, text: B) -> Distance {
+        let mut state = self.initial_state();
+        for &b in text.as_ref() {
+            state = self.transition(state, b);
+        }
+        self.distance(state)
+    }
+
+    /// Returns the Levenshtein distance associated to the
+    /// current state.
+    pub fn distance(&self, state_id: u32) -> Distance {
+        self.distances[state_id as usize]
+    }
+
+    /// Returns the number of states in the `CompactDFA`.
+    pub fn num_states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns the destination state reached after consuming a given byte.
+    pub fn transition(&self, from_state_id: u32, b: u8) -> u32 {
+        self.transitions[from_state_id as usize][b as usize].into()
+    }
+}
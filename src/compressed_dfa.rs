@@ -0,0 +1,145 @@
+use super::{Distance, DFA};
+
+/// Maximum number of runs a row can hold before we fall back
+/// to a full transition array.
+///
+/// Past this point, a `(u8, u8, u32)` run list is larger than
+/// the 256 `u32` of a full row.
+const MAX_NUM_RUNS: usize = 128;
+
+/// Transitions of a given state, stored as runs of consecutive bytes
+/// sharing the same destination state.
+///
+/// Rows with too many runs are stored as a full array.
+#[derive(Clone)]
+pub enum CompressedTransitionRow {
+    /// Sorted `(start_byte, end_byte_inclusive, destination state)` triplets
+    /// covering the 256 bytes.
+    Runs(Vec<(u8, u8, u32)>),
+    /// All of the 256 destination states.
+    Full(Box<[u32; 256]>),
+}
+
+impl CompressedTransitionRow {
+    fn from_row(row: &[u32; 256]) -> CompressedTransitionRow {
+        let mut runs: Vec<(u8, u8, u32)> = Vec::new();
+        for (b, &dest) in row.iter().enumerate() {
+            let b = b as u8;
+            match runs.last_mut() {
+                Some(&mut (_, ref mut end, run_dest)) if run_dest == dest => {
+                    *end = b;
+                }
+                _ => {
+                    if runs.len() == MAX_NUM_RUNS {
+                        return CompressedTransitionRow::Full(Box::new(*row));
+                    }
+                    runs.push((b, b, dest));
+                }
+            }
+        }
+        runs.shrink_to_fit();
+        CompressedTransitionRow::Runs(runs)
+    }
+
+    /// Returns the destination state reached after consuming a given byte.
+    pub fn transition(&self, b: u8) -> u32 {
+        match *self {
+            CompressedTransitionRow::Runs(ref runs) => {
+                let run_id = runs.partition_point(|&(_, end, _)| end < b);
+                runs[run_id].2
+            }
+            CompressedTransitionRow::Full(ref row) => row[b as usize],
+        }
+    }
+
+    /// Returns the number of bytes used by the row on the heap.
+    fn heap_size(&self) -> usize {
+        match *self {
+            CompressedTransitionRow::Runs(ref runs) => runs.len() * 8,
+            CompressedTransitionRow::Full(_) => 256 * 4,
+        }
+    }
+}
+
+/// A [DFA](./struct.DFA.html) whose transition table is compressed using
+/// run-length encoding.
+///
+/// Most of the transitions of a Levenshtein DFA lead to a handful of states,
+/// and bytes leading to the same state tend to be contiguous (e.g. all of the
+/// leading bytes of 2-bytes utf-8 encoded characters).
+/// For a DFA with `max_distance = 2` and no transpositions built for the
+/// 20 characters ASCII query `"abcdefghijklmnopqrst"`, the transition table
+/// is 16 times smaller (35kB instead of 564kB).
+///
+/// The price to pay is a binary search for each transition.
+#[derive(Clone)]
+pub struct CompressedDFA {
+    rows: Vec<CompressedTransitionRow>,
+    distances: Vec<Distance>,
+    initial_state: u32,
+}
+
+impl CompressedDFA {
+    pub(crate) fn from_dfa(dfa: &DFA) -> CompressedDFA {
+        let rows = (0..dfa.num_states() as u32)
+            .map(|state| {
+                let mut row = [0u32; 256];
+                for (b, dest) in dfa.transitions_from(state) {
+                    row[b as usize] = dest;
+                }
+                CompressedTransitionRow::from_row(&row)
+            })
+            .collect();
+        let distances = dfa.states_iter().map(|(_, distance)| distance).collect();
+        CompressedDFA {
+            rows,
+            distances,
+            initial_state: dfa.initial_state(),
+        }
+    }
+
+    /// Returns the initial state
+    pub fn initial_state(&self) -> u32 {
+        self.initial_state
+    }
+
+    /// Helper function that consumes all of the bytes
+    /// a sequence of bytes and returns the resulting
+    /// distance.
+    pub fn eval<B: AsRef<[u8]>>(&self, text: B) -> Distance {
+        let mut state = self.initial_state();
+        for &b in text.as_ref() {
+            state = self.transition(state, b);
+        }
+        self.distance(state)
+    }
+
+    /// Returns the Levenshtein distance associated to the
+    /// current state.
+    pub fn distance(&self, state_id: u32) -> Distance {
+        self.distances[state_id as usize]
+    }
+
+    /// Returns the number of states in the `CompressedDFA`.
+    pub fn num_states(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns the destination state reached after consuming a given byte.
+    pub fn transition(&self, from_state_id: u32, b: u8) -> u32 {
+        self.rows[from_state_id as usize].transition(b)
+    }
+
+    /// Returns the transitions of a given state.
+    pub fn row(&self, state_id: u32) -> &CompressedTransitionRow {
+        &self.rows[state_id as usize]
+    }
+
+    /// Returns the number of bytes used by the transition table.
+    pub fn transition_table_size(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| std::mem::size_of::<CompressedTransitionRow>() + row.heap_size())
+            .sum()
+    }
+}
@@ -1,4 +1,23 @@
+use super::alignment::{Alignment, AlignmentQuery};
+use super::class_dfa::{ByteClassError, ClassDFA};
+use super::compact_dfa::{CompactDFA, OverflowError};
+use super::CompressedDFA;
 use super::Distance;
+use super::Index;
+use super::ParametricState;
+use super::{AcceptedIter, Trie};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::{Deref, RangeInclusive};
+use std::sync::Arc;
+
+#[cfg(feature = "regex_automata")]
+use regex_automata::{
+    dfa::StartError,
+    util::{primitives::StateID, start},
+    Anchored, PatternID,
+};
 
 /// Sink state. See [DFA](./index.html)
 pub const SINK_STATE: u32 = 0u32;
@@ -19,6 +38,11 @@ pub const SINK_STATE: u32 = 0u32;
 /// This property can be exploited to abort further
 /// evaluation.
 ///
+/// The automaton is total: every state has a transition to an existing
+/// state for each of the 256 byte values. See [is_total(...)](#method.is_total).
+/// This invariant holds for the `DFA`s built by this crate, and is checked by
+/// [validate(...)](#method.validate) for the other ones.
+///
 ///
 /// # Usage
 ///
@@ -46,6 +70,9 @@ pub struct DFA {
     transitions: Vec<[u32; 256]>,
     distances: Vec<Distance>,
     initial_state: u32,
+    parametric_states: Vec<Option<ParametricState>>,
+    // The query the `DFA` was built for, used by `align`.
+    alignment_query: Option<AlignmentQuery>,
 }
 
 impl DFA {
@@ -65,25 +92,1274 @@ impl DFA {
         self.distance(state)
     }
 
+    /// Same as [eval(...)](#method.eval), but checks that `text` is `expected_len`
+    /// bytes long.
+    ///
+    /// This catches truncated texts, for instance when evaluating fixed-size
+    /// windows of a larger buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `text` is not `expected_len`.
+    pub fn consuming_distance_with_length_check<B: AsRef<[u8]>>(
+        &self,
+        text: B,
+        expected_len: usize,
+    ) -> Distance {
+        let text = text.as_ref();
+        assert_eq!(
+            text.len(),
+            expected_len,
+            "Text length does not match the expected length"
+        );
+        self.eval(text)
+    }
+
+    /// Same as [eval(...)](#method.eval), for the bytes `text[start..end]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start > end` or if `end > text.len()`.
+    pub fn eval_range(&self, text: &[u8], start: usize, end: usize) -> Distance {
+        assert!(start <= end, "Range start is larger than the range end");
+        assert!(
+            end <= text.len(),
+            "Range end is larger than the text length"
+        );
+        self.eval(&text[start..end])
+    }
+
+    /// Returns the minimum of the distances of the prefixes of `text`, i.e.
+    /// `min(self.eval(&text[..i]) for i in 0..=text.len())`.
+    ///
+    /// The `text` is evaluated only once, and evaluation stops as soon as
+    /// the sink state is reached. This is the distance that a `DFA` built by
+    /// [build_prefix_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_prefix_dfa)
+    /// would return.
+    pub fn eval_prefix_distance<B: AsRef<[u8]>>(&self, text: B) -> Distance {
+        let mut state = self.initial_state();
+        let mut min_distance = self.distance(state);
+        for &b in text.as_ref() {
+            state = self.transition(state, b);
+            if state == SINK_STATE {
+                break;
+            }
+            min_distance = min_distance.min(self.distance(state));
+        }
+        min_distance
+    }
+
+    /// Same as [eval(...)](#method.eval), for a string slice.
+    pub fn eval_str(&self, s: &str) -> Distance {
+        self.eval(s.as_bytes())
+    }
+
+    /// Same as [eval(...)](#method.eval), for an owned `String`.
+    #[allow(clippy::ptr_arg)]
+    pub fn eval_string(&self, s: &String) -> Distance {
+        self.eval_str(s)
+    }
+
+    /// Same as [eval(...)](#method.eval), for a slice of bytes.
+    pub fn eval_slice(&self, bytes: &[u8]) -> Distance {
+        self.eval(bytes)
+    }
+
+    /// Evaluates each of the `texts`, and returns their distances in the same order.
+    pub fn eval_all<'a>(&self, texts: impl IntoIterator<Item = &'a str>) -> Vec<Distance> {
+        texts.into_iter().map(|text| self.eval(text)).collect()
+    }
+
+    /// Returns the number of `texts` whose distance is `Distance::Exact(_)`.
+    ///
+    /// This is equivalent to
+    ///
+    /// ```ignored
+    /// dfa.eval_all(texts)
+    ///     .iter()
+    ///     .filter(|d| matches!(d, Distance::Exact(_)))
+    ///     .count()
+    /// ```
+    ///
+    /// but does not allocate, and stops evaluating a text as soon
+    /// as the sink state is reached.
+    pub fn eval_count_matches<'a>(&self, texts: impl IntoIterator<Item = &'a str>) -> usize {
+        texts
+            .into_iter()
+            .filter(|text| {
+                let mut state = self.initial_state;
+                for &b in text.as_bytes() {
+                    state = self.transition(state, b);
+                    if state == SINK_STATE {
+                        return false;
+                    }
+                }
+                matches!(self.distance(state), Distance::Exact(_))
+            })
+            .count()
+    }
+
+    /// Same as [eval(...)](#method.eval), but consumes at most `max_transitions` bytes.
+    ///
+    /// Returns `None` if the distance could not be computed within this limit.
+    /// Evaluation stops early if the sink state is reached, in which case the distance
+    /// is known regardless of the remaining bytes.
+    pub fn eval_with_limit<B: AsRef<[u8]>>(
+        &self,
+        text: B,
+        max_transitions: usize,
+    ) -> Option<Distance> {
+        let mut state = self.initial_state();
+        for (i, &b) in text.as_ref().iter().enumerate() {
+            if state == SINK_STATE {
+                break;
+            }
+            if i == max_transitions {
+                return None;
+            }
+            state = self.transition(state, b);
+        }
+        Some(self.distance(state))
+    }
+
+    /// Evaluates `text` and stops at the first accepting state encountered.
+    ///
+    /// Returns the number of bytes consumed to reach that state, the state itself
+    /// and its distance, or `None` if no accepting state is reached.
+    ///
+    /// Note that the returned distance is not necessarily the minimum distance
+    /// encountered along the path.
+    pub fn first_accepting_state_on_path<B: AsRef<[u8]>>(
+        &self,
+        text: B,
+    ) -> Option<(usize, u32, Distance)> {
+        let mut state = self.initial_state();
+        if let Distance::Exact(d) = self.distance(state) {
+            return Some((0, state, Distance::Exact(d)));
+        }
+        for (i, &b) in text.as_ref().iter().enumerate() {
+            state = self.transition(state, b);
+            if state == SINK_STATE {
+                return None;
+            }
+            if let Distance::Exact(d) = self.distance(state) {
+                return Some((i + 1, state, Distance::Exact(d)));
+            }
+        }
+        None
+    }
+
+    /// Evaluates `text` and returns all of the byte offsets `i` such that
+    /// the prefix `text[..i]` is accepted, together with its distance.
+    ///
+    /// Evaluation stops as soon as the sink state is reached.
+    pub fn all_accepting_positions<B: AsRef<[u8]>>(&self, text: B) -> Vec<(usize, Distance)> {
+        let mut positions = Vec::new();
+        let mut state = self.initial_state();
+        if let Distance::Exact(d) = self.distance(state) {
+            positions.push((0, Distance::Exact(d)));
+        }
+        for (i, &b) in text.as_ref().iter().enumerate() {
+            state = self.transition(state, b);
+            if state == SINK_STATE {
+                break;
+            }
+            if let Distance::Exact(d) = self.distance(state) {
+                positions.push((i + 1, Distance::Exact(d)));
+            }
+        }
+        positions
+    }
+
+    /// Returns the offset of the byte of `text` whose consumption leads to the sink state,
+    /// or `None` if the sink state is never reached.
+    ///
+    /// Past this byte, the distance is guaranteed to exceed `max_distance`
+    /// whatever the remaining bytes are.
+    pub fn first_mismatch_byte<B: AsRef<[u8]>>(&self, text: B) -> Option<usize> {
+        let mut state = self.initial_state();
+        for (i, &b) in text.as_ref().iter().enumerate() {
+            state = self.transition(state, b);
+            if state == SINK_STATE {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Returns the length of the longest common prefix of `text1` and `text2`
+    /// that leads to the same state.
+    ///
+    /// Both texts are consumed in lockstep, stopping at the first byte after
+    /// which their states differ. The bytes of the prefixes themselves may differ,
+    /// for instance once both texts have reached the sink state.
+    ///
+    /// When evaluating sorted terms, the state reached for the previous term after
+    /// this many bytes can be reused, and only the remaining bytes of the next term
+    /// need to be consumed.
+    pub fn common_prefix_states(&self, text1: &[u8], text2: &[u8]) -> usize {
+        let mut state1 = self.initial_state();
+        let mut state2 = self.initial_state();
+        for (i, (&b1, &b2)) in text1.iter().zip(text2.iter()).enumerate() {
+            state1 = self.transition(state1, b1);
+            state2 = self.transition(state2, b2);
+            if state1 != state2 {
+                return i;
+            }
+        }
+        text1.len().min(text2.len())
+    }
+
+    /// Consumes `bytes` starting from `initial_state`, and returns the
+    /// resulting state and its distance.
+    ///
+    /// Together with [common_prefix_states(...)](#method.common_prefix_states),
+    /// this makes it possible to only consume the bytes that differ from the
+    /// previously evaluated text.
+    pub fn resume_eval(&self, initial_state: u32, bytes: &[u8]) -> (u32, Distance) {
+        let mut state = initial_state;
+        for &b in bytes {
+            state = self.transition(state, b);
+        }
+        (state, self.distance(state))
+    }
+
+    /// Returns an optimal alignment of the `text` with the query of the `DFA`,
+    /// describing which chars were matched, substituted, inserted, deleted
+    /// or transposed.
+    ///
+    /// The alignment follows the edit model of the `DFA`: transpositions are only
+    /// used if the `DFA` assigns them a distance of 1, and for a prefix `DFA`, the
+    /// query is aligned with the prefix of the text after which the `DFA`
+    /// stops changing state.
+    /// Whenever `distance` is exact, the number of operations other than
+    /// `AlignKind::Match` is equal to it. Otherwise the operations of an optimal
+    /// alignment are returned nonetheless.
+    ///
+    /// The text is decoded as UTF-8, invalid sequences being replaced by `U+FFFD`.
+    ///
+    /// Returns `None` if the `DFA` does not keep track of its query, i.e. if it was
+    /// not built by [ParametricDFA::build_dfa(...)](./struct.ParametricDFA.html#method.build_dfa).
+    pub fn align<B: AsRef<[u8]>>(&self, text: B) -> Option<Alignment> {
+        let alignment_query = self.alignment_query.as_ref()?;
+        let text = text.as_ref();
+        let text_chars: Vec<char> = String::from_utf8_lossy(text).chars().collect();
+        let aligned_len = if alignment_query.prefix {
+            self.prefix_len(&alignment_query.chars, &text_chars)
+        } else {
+            text_chars.len()
+        };
+        Some(Alignment {
+            distance: self.eval(text),
+            ops: alignment_query.align(&text_chars[..aligned_len]),
+        })
+    }
+
+    // Returns the number of chars of `text` after which a prefix `DFA` built for
+    // `query` stops changing state, i.e. the length of the prefix of `text`
+    // its distance is computed for.
+    fn prefix_len(&self, query: &[char], text: &[char]) -> usize {
+        // The chars that do not appear in the query all lead to the same
+        // state as `other_char`.
+        let other_char = (0u32..)
+            .filter_map(char::from_u32)
+            .find(|c| !query.contains(c))
+            .expect("The query cannot contain every char");
+        let mut buffer = [0u8; 4];
+        let mut consume = |state: u32, c: char| {
+            c.encode_utf8(&mut buffer)
+                .bytes()
+                .fold(state, |state, b| self.transition(state, b))
+        };
+        let mut state = self.initial_state;
+        for (len, &c) in text.iter().enumerate() {
+            if state != SINK_STATE
+                && query
+                    .iter()
+                    .chain(Some(&other_char))
+                    .all(|&c| consume(state, c) == state)
+            {
+                return len;
+            }
+            state = consume(state, c);
+        }
+        text.len()
+    }
+
+    pub(crate) fn with_alignment_query(mut self, alignment_query: AlignmentQuery) -> DFA {
+        self.alignment_query = Some(alignment_query);
+        self
+    }
+
     /// Returns the Levenshtein distance associated to the
     /// current state.
+    #[inline(always)]
     pub fn distance(&self, state_id: u32) -> Distance {
         self.distances[state_id as usize]
     }
 
+    /// Returns the range of the exact distances reported by the `DFA`, or `None`
+    /// if the `DFA` does not accept any string.
+    ///
+    /// For a DFA built for `max_distance = 2`, the range is typically `0..=2`.
+    pub fn accepting_distance_range(&self) -> Option<RangeInclusive<u8>> {
+        let mut exact_distances = self
+            .distances
+            .iter()
+            .filter_map(|distance| match *distance {
+                Distance::Exact(d) => Some(d),
+                Distance::AtLeast(_) => None,
+            });
+        let first = exact_distances.next()?;
+        let (min, max) =
+            exact_distances.fold((first, first), |(min, max), d| (min.min(d), max.max(d)));
+        Some(min..=max)
+    }
+
+    /// Returns true iff the `DFA` does not accept any string, that is
+    /// if none of its states has an exact distance.
+    pub fn is_empty_language(&self) -> bool {
+        self.distances
+            .iter()
+            .all(|distance| matches!(distance, Distance::AtLeast(_)))
+    }
+
+    /// Returns the ids of all of the states with distance `Distance::Exact(d)`.
+    pub fn states_at_distance(&self, d: u8) -> Vec<u32> {
+        self.distances
+            .iter()
+            .enumerate()
+            .filter(|&(_, &distance)| distance == Distance::Exact(d))
+            .map(|(state, _)| state as u32)
+            .collect()
+    }
+
+    /// Rejects the states whose exact distance does not belong to `range`.
+    ///
+    /// States farther than `range.end()` report a distance of
+    /// `Distance::AtLeast(range.end() + 1)`, while states closer than
+    /// `range.start()` report `Distance::AtLeast(0)`, which does not claim
+    /// any bound on their distance. The states that already have an inexact
+    /// distance are left untouched.
+    pub(crate) fn restrict_distances(mut self, range: RangeInclusive<u8>) -> DFA {
+        // The alignments would not be consistent with the restricted distances.
+        self.alignment_query = None;
+        for distance in &mut self.distances {
+            if let Distance::Exact(d) = *distance {
+                if d < *range.start() {
+                    *distance = Distance::AtLeast(0);
+                } else if d > *range.end() {
+                    *distance = Distance::AtLeast(range.end() + 1u8);
+                }
+            }
+        }
+        self
+    }
+
+    /// Returns an iterator over the states of the `DFA` and their distances.
+    pub fn states_iter(&self) -> impl Iterator<Item = (u32, Distance)> + '_ {
+        self.distances
+            .iter()
+            .enumerate()
+            .map(|(state, &distance)| (state as u32, distance))
+    }
+
+    /// Returns the parametric state a given state originates from.
+    ///
+    /// This is only available for the DFAs built by
+    /// [build_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_dfa)
+    /// and [build_prefix_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_prefix_dfa).
+    /// `None` is returned for the intermediary states
+    /// reached in the middle of a multibyte utf-8 character.
+    pub fn parametric_state_for_dfa_state(&self, dfa_state: u32) -> Option<ParametricState> {
+        self.parametric_states
+            .get(dfa_state as usize)
+            .cloned()
+            .flatten()
+    }
+
+    /// Returns a description of the UTF-8 decoding stage of a given state: either
+    /// a char boundary, or the number of bytes of a multibyte utf-8 character
+    /// that remain to be consumed.
+    ///
+    /// Like [parametric_state_for_dfa_state(...)](#method.parametric_state_for_dfa_state),
+    /// this is only available for the DFAs built by
+    /// [build_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_dfa)
+    /// and [build_prefix_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_prefix_dfa).
+    /// `None` is also returned for the states that cannot be reached from the initial state.
+    pub fn state_to_utf8_description(&self, state: u32) -> Option<String> {
+        if self.parametric_states.is_empty() {
+            return None;
+        }
+        match self
+            .utf8_remaining_bytes()
+            .get(state as usize)
+            .cloned()
+            .flatten()?
+        {
+            0 => Some("char boundary".to_string()),
+            1 => Some("expecting 1 more byte of a multibyte char".to_string()),
+            num_bytes => Some(format!(
+                "expecting {} more bytes of a multibyte char",
+                num_bytes
+            )),
+        }
+    }
+
+    // Returns, for each state reachable from the initial state, the number of bytes
+    // of the current utf-8 character that remain to be consumed.
+    fn utf8_remaining_bytes(&self) -> Vec<Option<u8>> {
+        let mut remaining_bytes: Vec<Option<u8>> = vec![None; self.num_states()];
+        remaining_bytes[self.initial_state as usize] = Some(0);
+        let mut stack = vec![self.initial_state];
+        while let Some(state) = stack.pop() {
+            let num_bytes = remaining_bytes[state as usize].unwrap_or(0);
+            for (b, dest) in self.transitions_from(state) {
+                let dest_num_bytes = match (num_bytes, b) {
+                    (0, 0..=127) => 0,
+                    // Continuation bytes are not expected at a char boundary.
+                    (0, 128..=191) => continue,
+                    (0, 192..=223) => 1,
+                    (0, 224..=239) => 2,
+                    (0, _) => 3,
+                    (num_bytes, _) => num_bytes - 1,
+                };
+                if remaining_bytes[dest as usize].is_none() {
+                    remaining_bytes[dest as usize] = Some(dest_num_bytes);
+                    stack.push(dest);
+                }
+            }
+        }
+        remaining_bytes
+    }
+
     /// Returns the number of states in the `DFA`.
     pub fn num_states(&self) -> usize {
         self.transitions.len()
     }
 
+    /// Returns the number of states whose distance is `Distance::Exact(d)`.
+    pub fn count_states_with_distance(&self, d: u8) -> usize {
+        self.distances
+            .iter()
+            .filter(|&&distance| distance == Distance::Exact(d))
+            .count()
+    }
+
+    /// Returns the number of states for each distance.
+    ///
+    /// The entry `d` of the histogram is the number of states whose distance is
+    /// `Distance::Exact(d)`, and the last entry is the number of states whose
+    /// distance is `Distance::AtLeast(_)`, including the sink state.
+    ///
+    /// The distance of the sink state, `Distance::AtLeast(max_distance + 1)`, gives
+    /// the number of entries: `max_distance + 2`.
+    pub fn distance_histogram(&self) -> Vec<usize> {
+        let sink_len = self
+            .distances
+            .get(SINK_STATE as usize)
+            .map(|distance| distance.distance_value() as usize + 1)
+            .unwrap_or(1);
+        let len = self
+            .distances
+            .iter()
+            .filter_map(|distance| match *distance {
+                Distance::Exact(d) => Some(d as usize + 2),
+                Distance::AtLeast(_) => None,
+            })
+            .fold(sink_len, usize::max);
+        let mut histogram = vec![0; len];
+        for distance in &self.distances {
+            match *distance {
+                Distance::Exact(d) => histogram[d as usize] += 1,
+                Distance::AtLeast(_) => histogram[len - 1] += 1,
+            }
+        }
+        histogram
+    }
+
     /// Returns the destination state reached after consuming a given byte.
+    #[inline(always)]
     pub fn transition(&self, from_state_id: u32, b: u8) -> u32 {
         self.transitions[from_state_id as usize][b as usize]
     }
+
+    /// Returns an iterator over the 256 transitions `(byte, destination state)`
+    /// starting from a given state.
+    pub fn transitions_from(&self, state: u32) -> impl Iterator<Item = (u8, u32)> + '_ {
+        self.transitions[state as usize]
+            .iter()
+            .enumerate()
+            .map(|(b, &dest)| (b as u8, dest))
+    }
+
+    /// Returns all of the transitions `(from_state, byte, to_state)` of the `DFA`,
+    /// including those leading to the sink state.
+    ///
+    /// The result has `256 * num_states()` entries, ordered by state and byte.
+    pub fn transition_graph(&self) -> Vec<(u32, u8, u32)> {
+        (0..self.num_states() as u32)
+            .flat_map(|from| {
+                self.transitions_from(from)
+                    .map(move |(b, to)| (from, b, to))
+            })
+            .collect()
+    }
+
+    /// Same as [transition_graph(...)](#method.transition_graph), but the bytes
+    /// leading from a state to the same destination are grouped together.
+    ///
+    /// Entries are ordered by state, then by their smallest byte, and the bytes
+    /// of an entry are sorted.
+    pub fn condensed_transition_graph(&self) -> Vec<(u32, Vec<u8>, u32)> {
+        let mut graph = Vec::new();
+        for from in 0..self.num_states() as u32 {
+            let mut edges: Vec<(Vec<u8>, u32)> = Vec::new();
+            for (b, to) in self.transitions_from(from) {
+                if let Some(edge) = edges.iter_mut().find(|(_, dest)| *dest == to) {
+                    edge.0.push(b);
+                } else {
+                    edges.push((vec![b], to));
+                }
+            }
+            graph.extend(edges.into_iter().map(|(bytes, to)| (from, bytes, to)));
+        }
+        graph
+    }
+
+    /// Returns the default transition of a given state, defined as the
+    /// most common destination among its 256 transitions.
+    ///
+    /// Ties are broken in favor of the lowest state id.
+    pub fn default_transition(&self, state: u32) -> u32 {
+        let mut dests = self.transitions[state as usize];
+        dests.sort_unstable();
+        let mut default_dest = dests[0];
+        let mut default_count = 0;
+        for run in dests.chunk_by(|left, right| left == right) {
+            if run.len() > default_count {
+                default_dest = run[0];
+                default_count = run.len();
+            }
+        }
+        default_dest
+    }
+
+    /// Returns an iterator over the transitions `(byte, destination state)`
+    /// starting from a given state, that do not lead to its
+    /// [default transition](#method.default_transition).
+    pub fn non_default_transitions_from(&self, state: u32) -> impl Iterator<Item = (u8, u32)> + '_ {
+        let default_dest = self.default_transition(state);
+        self.transitions_from(state)
+            .filter(move |&(_, dest)| dest != default_dest)
+    }
+
+    /// Returns the smallest unsigned integer type able to represent
+    /// all of the state ids of the `DFA`.
+    pub fn state_id_type(&self) -> StateIdType {
+        let num_states = self.num_states();
+        if num_states <= usize::from(u8::MAX) + 1 {
+            StateIdType::U8
+        } else if num_states <= usize::from(u16::MAX) + 1 {
+            StateIdType::U16
+        } else {
+            StateIdType::U32
+        }
+    }
+
+    /// Returns statistics about the density of the transition table.
+    ///
+    /// See [TransitionStats](./struct.TransitionStats.html).
+    pub fn transition_stats(&self) -> TransitionStats {
+        let total_transitions = self.num_states() * 256;
+        let mut sink_transitions = 0;
+        let mut non_default_transitions = 0;
+        for state in 0..self.num_states() as u32 {
+            sink_transitions += self
+                .transitions_from(state)
+                .filter(|&(_, dest)| dest == SINK_STATE)
+                .count();
+            non_default_transitions += self.non_default_transitions_from(state).count();
+        }
+        let density = if total_transitions == 0 {
+            0f64
+        } else {
+            (total_transitions - sink_transitions) as f64 / total_transitions as f64
+        };
+        TransitionStats {
+            total_transitions,
+            sink_transitions,
+            non_default_transitions,
+            density,
+        }
+    }
+
+    /// Returns the number of distinct states, other than the sink state,
+    /// that are the destination of at least one transition.
+    ///
+    /// This measures how branchy the `DFA` is, and helps deciding between a dense
+    /// and a [compressed](#method.compress) representation.
+    pub fn count_unique_destination_states(&self) -> usize {
+        let mut is_destination = vec![false; self.num_states()];
+        for state_transitions in &self.transitions {
+            for &dest in state_transitions.iter() {
+                is_destination[dest as usize] = true;
+            }
+        }
+        is_destination[SINK_STATE as usize] = false;
+        is_destination.into_iter().filter(|&dest| dest).count()
+    }
+
+    /// Partitions the 256 byte values into classes of bytes that lead
+    /// to the same state, whatever the state they are consumed from.
+    ///
+    /// Classes are ordered by their smallest byte, and the bytes of a class
+    /// are sorted.
+    pub fn byte_equivalence_classes(&self) -> Vec<Vec<u8>> {
+        let mut index: Index<Vec<u32>> = Index::new();
+        let mut classes: Vec<Vec<u8>> = Vec::new();
+        for b in 0..=255u8 {
+            let column: Vec<u32> = self
+                .transitions
+                .iter()
+                .map(|state_transitions| state_transitions[b as usize])
+                .collect();
+            let class_id = index.get_or_allocate(&column) as usize;
+            if class_id == classes.len() {
+                classes.push(Vec::new());
+            }
+            classes[class_id].push(b);
+        }
+        classes
+    }
+
+    /// Returns a copy of the `DFA` whose transitions are indexed by classes of bytes.
+    ///
+    /// `classes` maps each byte to its class, for instance as given by
+    /// [byte_equivalence_classes(...)](#method.byte_equivalence_classes).
+    ///
+    /// Returns a `ByteClassError` if two bytes of the same class lead
+    /// to different states.
+    /// See [ClassDFA](./struct.ClassDFA.html).
+    pub fn remap_to_byte_classes(&self, classes: &[u8; 256]) -> Result<ClassDFA, ByteClassError> {
+        ClassDFA::from_dfa(self, classes)
+    }
+
+    /// Shrinks the capacity of the internal buffers as much as possible.
+    ///
+    /// The builders may over-allocate. This is worth calling
+    /// on `DFA`s that are kept alive for a long time.
+    pub fn shrink_to_fit(&mut self) {
+        self.transitions.shrink_to_fit();
+        self.distances.shrink_to_fit();
+        self.parametric_states.shrink_to_fit();
+    }
+
+    /// Returns a copy of the `DFA` with a run-length encoded transition table.
+    ///
+    /// See [CompressedDFA](./struct.CompressedDFA.html).
+    pub fn compress(&self) -> CompressedDFA {
+        CompressedDFA::from_dfa(self)
+    }
+
+    /// Converts the `DFA` into a [CompactDFA](./struct.CompactDFA.html)
+    /// storing its state ids as `u16`.
+    ///
+    /// Returns an `OverflowError` if the `DFA` has more than `65_536` states.
+    /// See [state_id_type(...)](#method.state_id_type).
+    pub fn to_u16_compact(self) -> Result<CompactDFA<u16>, OverflowError> {
+        CompactDFA::from_dfa(self)
+    }
+
+    /// Returns an iterator over the terms of the `trie` accepted by the `DFA`.
+    ///
+    /// The trie and the `DFA` are walked simultaneously, and the branches of
+    /// the trie that lead to the sink state are not explored.
+    /// The terms are not returned in any particular order.
+    pub fn iter_accepted_in_trie<'t, T: Trie>(&self, trie: &'t T) -> AcceptedIter<'t, '_, T> {
+        AcceptedIter::new(self, trie)
+    }
+
+    /// Wraps the `DFA` into an [OwnedDFA](./struct.OwnedDFA.html),
+    /// that can be cloned cheaply.
+    pub fn into_owned(self) -> OwnedDFA {
+        OwnedDFA(Arc::new(self))
+    }
+
+    /// Returns true iff the automaton contains a cycle, ignoring the
+    /// self-loops of the sink state.
+    ///
+    /// DFAs built by [build_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_dfa)
+    /// are acyclic: every byte consumed either brings us closer to the end
+    /// of the query or increases the distance, so that all paths eventually
+    /// end up in the sink state.
+    ///
+    /// DFAs built by
+    /// [build_prefix_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_prefix_dfa)
+    /// on the other hand loop on the states in which the whole query has been matched,
+    /// as any byte appended to the text leaves the prefix distance unchanged.
+    pub fn is_cyclic(&self) -> bool {
+        const WHITE: u8 = 0;
+        const GREY: u8 = 1;
+        const BLACK: u8 = 2;
+        let mut colors = vec![WHITE; self.num_states()];
+        colors[SINK_STATE as usize] = BLACK;
+        // Stack of (state, next byte to explore).
+        let mut stack: Vec<(u32, usize)> = Vec::new();
+        for root in 0..self.num_states() as u32 {
+            if colors[root as usize] != WHITE {
+                continue;
+            }
+            colors[root as usize] = GREY;
+            stack.push((root, 0));
+            while let Some((state, b)) = stack.pop() {
+                if b == 256 {
+                    colors[state as usize] = BLACK;
+                    continue;
+                }
+                stack.push((state, b + 1));
+                let dest = self.transition(state, b as u8);
+                match colors[dest as usize] {
+                    WHITE => {
+                        colors[dest as usize] = GREY;
+                        stack.push((dest, 0));
+                    }
+                    GREY => {
+                        return true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the states of the automaton in topological order, or `None`
+    /// if the automaton is [cyclic](#method.is_cyclic).
+    ///
+    /// The transitions starting from the sink state are ignored, so that the
+    /// sink state always comes last.
+    pub fn topological_sort(&self) -> Option<Vec<u32>> {
+        let num_states = self.num_states();
+        let mut in_degrees = vec![0usize; num_states];
+        for state_transitions in self.transitions.iter().skip(SINK_STATE as usize + 1) {
+            for &dest in state_transitions.iter() {
+                in_degrees[dest as usize] += 1;
+            }
+        }
+        let mut stack: Vec<u32> = (0..num_states as u32)
+            .filter(|&state| state != SINK_STATE && in_degrees[state as usize] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(num_states);
+        while let Some(state) = stack.pop() {
+            order.push(state);
+            for &dest in self.transitions[state as usize].iter() {
+                in_degrees[dest as usize] -= 1;
+                if in_degrees[dest as usize] == 0 && dest != SINK_STATE {
+                    stack.push(dest);
+                }
+            }
+        }
+        if in_degrees[SINK_STATE as usize] != 0 || order.len() + 1 != num_states {
+            return None;
+        }
+        order.push(SINK_STATE);
+        Some(order)
+    }
+
+    /// Returns the reverse automaton.
+    ///
+    /// The reverse automaton reports, for the byte-wise reversal of a text,
+    /// the same exact distance as `self` reports for the text.
+    /// In other words, for any text `t` such that `self.eval(t)` is `Distance::Exact(d)`,
+    /// `self.reverse().eval(reversed(t))` is `Distance::Exact(d)` as well.
+    /// Texts that are not accepted are reported as `Distance::AtLeast(max_distance + 1)`.
+    ///
+    /// The reversed text is generally not valid utf-8, as the automaton works at the
+    /// byte level.
+    ///
+    /// The construction reverses all of the edges and determinizes the result using
+    /// the subset construction. Each state of the reverse automaton is a set of
+    /// `(state, distance)` pairs, recording which distance would be reached from `state`
+    /// by consuming the text read so far in the original direction.
+    pub fn reverse(&self) -> DFA {
+        let num_states = self.num_states();
+
+        // Only states reachable from the initial state matter.
+        let mut reachable = vec![false; num_states];
+        let mut queue = vec![self.initial_state];
+        reachable[self.initial_state as usize] = true;
+        while let Some(state) = queue.pop() {
+            for &dest in self.transitions[state as usize].iter() {
+                if !reachable[dest as usize] {
+                    reachable[dest as usize] = true;
+                    queue.push(dest);
+                }
+            }
+        }
+
+        let mut predecessors: Vec<Vec<(u8, u32)>> = vec![Vec::new(); num_states];
+        let mut max_exact_distance = 0u8;
+        let mut initial_multistate: Vec<(u32, u8)> = Vec::new();
+        for (state, state_transitions) in self.transitions.iter().enumerate() {
+            if !reachable[state] {
+                continue;
+            }
+            for (b, &dest) in state_transitions.iter().enumerate() {
+                predecessors[dest as usize].push((b as u8, state as u32));
+            }
+            if let Distance::Exact(d) = self.distances[state] {
+                max_exact_distance = max_exact_distance.max(d);
+                initial_multistate.push((state as u32, d));
+            }
+        }
+        let not_accepted = Distance::AtLeast(max_exact_distance + 1u8);
+
+        let mut index: Index<Vec<(u32, u8)>> = Index::new();
+        // The empty set is the sink state.
+        let sink_state = index.get_or_allocate(&Vec::new());
+        assert_eq!(sink_state, SINK_STATE);
+        let initial_state = index.get_or_allocate(&initial_multistate);
+
+        let mut transitions: Vec<[u32; 256]> = Vec::new();
+        let mut distances: Vec<Distance> = Vec::new();
+        let mut dests: Vec<Vec<(u32, u8)>> = vec![Vec::new(); 256];
+
+        for multistate_id in 0.. {
+            if multistate_id == index.len() {
+                break;
+            }
+            for dest in dests.iter_mut() {
+                dest.clear();
+            }
+            let mut distance = not_accepted;
+            for &(state, d) in index.get_from_id(multistate_id) {
+                if state == self.initial_state {
+                    distance = Distance::Exact(d);
+                }
+                for &(b, predecessor) in &predecessors[state as usize] {
+                    dests[b as usize].push((predecessor, d));
+                }
+            }
+            let mut multistate_transitions = [SINK_STATE; 256];
+            for (dest_id, dest) in multistate_transitions.iter_mut().zip(dests.iter_mut()) {
+                dest.sort_unstable();
+                dest.dedup();
+                *dest_id = index.get_or_allocate(dest);
+            }
+            transitions.push(multistate_transitions);
+            distances.push(distance);
+        }
+
+        DFA {
+            transitions,
+            distances,
+            initial_state,
+            parametric_states: Vec::new(),
+            alignment_query: None,
+        }
+    }
+
+    /// Returns a human readable label for the given state.
+    ///
+    /// The sink state is labelled `SINK`, the initial state `INIT`.
+    /// Other states are labelled after their distance, followed by
+    /// their rank among the other states sharing the same distance.
+    /// For instance, `d=2[3]` is the third state with distance `Exact(2)`,
+    /// and `d>=3[1]` the first state with distance `AtLeast(3)`.
+    pub fn state_name(&self, state: u32) -> String {
+        if state == SINK_STATE {
+            return "SINK".to_string();
+        }
+        if state == self.initial_state {
+            return "INIT".to_string();
+        }
+        let distance = self.distance(state);
+        // The sink and initial states are not counted, as they have their own label.
+        let rank = self.distances[..=state as usize]
+            .iter()
+            .enumerate()
+            .filter(|&(other_state, &other_distance)| {
+                other_state as u32 != SINK_STATE
+                    && other_state as u32 != self.initial_state
+                    && other_distance == distance
+            })
+            .count();
+        match distance {
+            Distance::Exact(d) => format!("d={}[{}]", d, rank),
+            Distance::AtLeast(d) => format!("d>={}[{}]", d, rank),
+        }
+    }
+
+    /// Writes a human readable transition table of the `DFA`.
+    ///
+    /// Each state is listed with its id and distance, followed by its
+    /// transitions. Consecutive bytes leading to the same state are
+    /// grouped in a range (e.g. `[a-z] -> 5`), and the transitions to the
+    /// [default_transition(...)](#method.default_transition) of the state
+    /// are summarized as `[everything else] -> 0`.
+    ///
+    /// Use `&mut std::io::stdout()` as `wtr` to print the table.
+    pub fn pretty_print<W: Write>(&self, wtr: &mut W) -> io::Result<()> {
+        for state in 0..self.num_states() as u32 {
+            let mut flags = String::new();
+            if state == self.initial_state {
+                flags.push_str(" (initial)");
+            }
+            if state == SINK_STATE {
+                flags.push_str(" (sink)");
+            }
+            writeln!(wtr, "state {}: {:?}{}", state, self.distance(state), flags)?;
+            let mut transitions = self.non_default_transitions_from(state).peekable();
+            while let Some((start, dest)) = transitions.next() {
+                let mut end = start;
+                while let Some(&(b, next_dest)) = transitions.peek() {
+                    if next_dest != dest || b != end + 1 {
+                        break;
+                    }
+                    end = b;
+                    transitions.next();
+                }
+                if start == end {
+                    writeln!(wtr, "  [{}] -> {}", byte_label(start), dest)?;
+                } else {
+                    writeln!(
+                        wtr,
+                        "  [{}-{}] -> {}",
+                        byte_label(start),
+                        byte_label(end),
+                        dest
+                    )?;
+                }
+            }
+            writeln!(
+                wtr,
+                "  [everything else] -> {}",
+                self.default_transition(state)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Creates a `DFA` from its raw parts: the transition table, the distance
+    /// associated to each state and the initial state.
+    ///
+    /// The parts are not checked. See [validate(...)](#method.validate).
+    pub fn from_raw_parts(
+        transitions: Vec<[u32; 256]>,
+        distances: Vec<Distance>,
+        initial_state: u32,
+    ) -> DFA {
+        DFA {
+            transitions,
+            distances,
+            initial_state,
+            parametric_states: Vec::new(),
+            alignment_query: None,
+        }
+    }
+
+    /// Decomposes the `DFA` into its raw parts: the transition table, the distance
+    /// associated to each state and the initial state.
+    pub fn into_raw_parts(self) -> (Vec<[u32; 256]>, Vec<Distance>, u32) {
+        (self.transitions, self.distances, self.initial_state)
+    }
+
+    /// Creates a `DFA` from its [parts](./struct.DFAParts.html).
+    ///
+    /// The parts are not checked. See [validate(...)](#method.validate).
+    pub fn from_parts(parts: DFAParts) -> DFA {
+        DFA::from_raw_parts(parts.transitions, parts.distances, parts.initial_state)
+    }
+
+    /// Decomposes the `DFA` into its [parts](./struct.DFAParts.html).
+    pub fn into_parts(self) -> DFAParts {
+        let (transitions, distances, initial_state) = self.into_raw_parts();
+        DFAParts {
+            transitions,
+            distances,
+            initial_state,
+        }
+    }
+
+    /// Serializes the `DFA` into bytes.
+    ///
+    /// The parametric states are not serialized.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let num_states = self.transitions.len();
+        let mut bytes = Vec::with_capacity(8 + num_states * (2 + 256 * 4));
+        self.serialize_to_writer(&mut bytes)
+            .expect("Writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Same as [to_bytes(...)](#method.to_bytes), but the bytes are written
+    /// to `wrt` as they are produced.
+    pub fn serialize_to_writer<W: Write>(&self, wrt: &mut W) -> io::Result<()> {
+        wrt.write_all(&(self.transitions.len() as u32).to_le_bytes())?;
+        wrt.write_all(&self.initial_state.to_le_bytes())?;
+        for &distance in &self.distances {
+            match distance {
+                Distance::Exact(d) => wrt.write_all(&[0u8, d])?,
+                Distance::AtLeast(d) => wrt.write_all(&[1u8, d])?,
+            }
+        }
+        let mut buffer = [0u8; 256 * 4];
+        for state_transitions in &self.transitions {
+            for (dest_bytes, &dest) in buffer.chunks_mut(4).zip(state_transitions.iter()) {
+                dest_bytes.copy_from_slice(&dest.to_le_bytes());
+            }
+            wrt.write_all(&buffer)?;
+        }
+        Ok(())
+    }
+
+    /// Deserializes a `DFA` written by [serialize_to_writer(...)](#method.serialize_to_writer)
+    /// or [to_bytes(...)](#method.to_bytes), reading exactly the bytes of the `DFA` from `rdr`.
+    ///
+    /// The resulting `DFA` is validated. If the bytes do not describe a valid `DFA`,
+    /// an error of kind `InvalidData` wrapping an [InvalidDFA](./enum.InvalidDFA.html)
+    /// is returned.
+    pub fn deserialize_from_reader<R: Read>(rdr: &mut R) -> io::Result<DFA> {
+        let mut header = [0u8; 8];
+        rdr.read_exact(&mut header)?;
+        let num_states = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let initial_state = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        // The vectors are not preallocated, so that a corrupted header
+        // cannot trigger a huge allocation.
+        let mut distances = Vec::new();
+        for _ in 0..num_states {
+            let mut distance = [0u8; 2];
+            rdr.read_exact(&mut distance)?;
+            distances.push(match distance {
+                [0, d] => Distance::Exact(d),
+                [1, d] => Distance::AtLeast(d),
+                [tag, _] => return Err(invalid_data(InvalidDFA::InvalidDistanceTag(tag))),
+            });
+        }
+        let mut transitions = Vec::new();
+        let mut buffer = [0u8; 256 * 4];
+        for _ in 0..num_states {
+            rdr.read_exact(&mut buffer)?;
+            let mut state_transitions = [SINK_STATE; 256];
+            for (dest, dest_bytes) in state_transitions.iter_mut().zip(buffer.chunks(4)) {
+                *dest = u32::from_le_bytes([
+                    dest_bytes[0],
+                    dest_bytes[1],
+                    dest_bytes[2],
+                    dest_bytes[3],
+                ]);
+            }
+            transitions.push(state_transitions);
+        }
+        let dfa = DFA::from_raw_parts(transitions, distances, initial_state);
+        dfa.validate().map_err(invalid_data)?;
+        Ok(dfa)
+    }
+
+    /// Deserializes a `DFA` serialized with [to_bytes(...)](#method.to_bytes).
+    ///
+    /// The resulting `DFA` is validated, and `bytes` must not contain anything
+    /// after the serialized `DFA`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<DFA, InvalidDFA> {
+        let mut rdr = bytes;
+        let dfa = DFA::deserialize_from_reader(&mut rdr).map_err(|err| {
+            // Reading from a slice only fails at the end of the input,
+            // or with one of the `InvalidDFA` errors raised while deserializing.
+            err.into_inner()
+                .and_then(|inner| inner.downcast::<InvalidDFA>().ok())
+                .map(|invalid_dfa| *invalid_dfa)
+                .unwrap_or(InvalidDFA::UnexpectedEndOfInput)
+        })?;
+        if !rdr.is_empty() {
+            return Err(InvalidDFA::TrailingBytes);
+        }
+        Ok(dfa)
+    }
+
+    /// Checks that the `DFA` is well-formed.
+    ///
+    /// All of the states referenced by the transition table and the initial state
+    /// must exist, each state must have a distance and the sink state must not
+    /// be accepting. All of the transitions of the sink state must lead to itself.
+    ///
+    /// Running a `DFA` that is not well-formed may panic.
+    pub fn validate(&self) -> Result<(), InvalidDFA> {
+        let num_states = self.transitions.len();
+        if num_states != self.distances.len() {
+            return Err(InvalidDFA::NumStatesMismatch {
+                num_transition_rows: num_states,
+                num_distances: self.distances.len(),
+            });
+        }
+        if num_states == 0 {
+            return Err(InvalidDFA::NoStates);
+        }
+        if self.initial_state as usize >= num_states {
+            return Err(InvalidDFA::InvalidState(self.initial_state));
+        }
+        if let Distance::Exact(_) = self.distances[SINK_STATE as usize] {
+            return Err(InvalidDFA::AcceptingSinkState);
+        }
+        if let Some(dest) = self.first_missing_destination() {
+            return Err(InvalidDFA::InvalidState(dest));
+        }
+        if self.transitions[SINK_STATE as usize]
+            .iter()
+            .any(|&dest| dest != SINK_STATE)
+        {
+            return Err(InvalidDFA::NonAbsorbingSinkState);
+        }
+        Ok(())
+    }
+
+    /// Returns true iff every state has a transition to an existing
+    /// state for each of the 256 byte values.
+    ///
+    /// The `DFA`s built by this crate are always total. A `DFA` created with
+    /// [from_raw_parts(...)](#method.from_raw_parts) may not be, in which case
+    /// [validate(...)](#method.validate) returns an error.
+    pub fn is_total(&self) -> bool {
+        self.first_missing_destination().is_none()
+    }
+
+    // Returns the first destination of the transition table that is not an existing state.
+    fn first_missing_destination(&self) -> Option<u32> {
+        let num_states = self.transitions.len();
+        self.transitions
+            .iter()
+            .flat_map(|state_transitions| state_transitions.iter())
+            .cloned()
+            .find(|&dest| dest as usize >= num_states)
+    }
+}
+
+/// Smallest unsigned integer type able to represent all of the state ids
+/// of a [DFA](./struct.DFA.html).
+///
+/// See [DFA::state_id_type(...)](./struct.DFA.html#method.state_id_type).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum StateIdType {
+    U8,
+    U16,
+    U32,
+}
+
+/// Statistics about the transition table of a [DFA](./struct.DFA.html),
+/// returned by [DFA::transition_stats(...)](./struct.DFA.html#method.transition_stats).
+///
+/// They help deciding between the dense representation of the `DFA` and
+/// a sparse one such as [CompressedDFA](./struct.CompressedDFA.html).
+///
+/// For the query `"Levenshtein"` without transpositions, the density is
+/// about `0.68` for `d=1` (86 states) and `0.81` for `d=2` (295 states),
+/// but only about 14% of the transitions are non-default in both cases:
+/// most rows are made of a few runs of identical transitions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransitionStats {
+    /// Number of entries of the transition table, i.e. `num_states * 256`.
+    pub total_transitions: usize,
+    /// Number of transitions leading to the sink state.
+    pub sink_transitions: usize,
+    /// Number of transitions that do not lead to the
+    /// [default transition](./struct.DFA.html#method.default_transition) of their state.
+    pub non_default_transitions: usize,
+    /// Ratio of the transitions that do not lead to the sink state.
+    pub density: f64,
+}
+
+/// The parts a [DFA](./struct.DFA.html) is made of.
+///
+/// See [DFA::into_parts(...)](./struct.DFA.html#method.into_parts)
+/// and [DFA::from_parts(...)](./struct.DFA.html#method.from_parts).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DFAParts {
+    /// For each state, the state reached after consuming each byte.
+    pub transitions: Vec<[u32; 256]>,
+    /// For each state, the distance associated to the state.
+    pub distances: Vec<Distance>,
+    /// The initial state of the `DFA`.
+    pub initial_state: u32,
+}
+
+/// A [DFA](./struct.DFA.html) shared behind an `Arc`.
+///
+/// Cloning an `OwnedDFA` does not copy the transition table, which makes
+/// it convenient to keep `DFA`s in a cache shared between threads.
+///
+/// See [DFA::into_owned(...)](./struct.DFA.html#method.into_owned).
+#[derive(Clone)]
+pub struct OwnedDFA(Arc<DFA>);
+
+impl Deref for OwnedDFA {
+    type Target = DFA;
+
+    fn deref(&self) -> &DFA {
+        &self.0
+    }
+}
+
+fn byte_label(b: u8) -> String {
+    if b.is_ascii_graphic() {
+        (b as char).to_string()
+    } else {
+        format!("\\x{:02x}", b)
+    }
+}
+
+/// Error returned when a [DFA](./struct.DFA.html) is not well-formed.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum InvalidDFA {
+    /// The number of rows of the transition table does not match
+    /// the number of distances.
+    NumStatesMismatch {
+        num_transition_rows: usize,
+        num_distances: usize,
+    },
+    /// The `DFA` does not have any state.
+    NoStates,
+    /// A state referenced by the `DFA` does not exist.
+    InvalidState(u32),
+    /// The sink state has an exact distance.
+    AcceptingSinkState,
+    /// A transition of the sink state leads to another state.
+    NonAbsorbingSinkState,
+    /// The serialized `DFA` is truncated.
+    UnexpectedEndOfInput,
+    /// The serialized `DFA` is followed by extra bytes.
+    TrailingBytes,
+    /// A serialized distance has an unknown tag.
+    InvalidDistanceTag(u8),
+}
+
+impl fmt::Display for InvalidDFA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidDFA::NumStatesMismatch {
+                num_transition_rows,
+                num_distances,
+            } => write!(
+                f,
+                "Number of transition rows ({}) does not match the number of distances ({})",
+                num_transition_rows, num_distances
+            ),
+            InvalidDFA::NoStates => write!(f, "DFA does not have any state"),
+            InvalidDFA::InvalidState(state) => write!(f, "State {} does not exist", state),
+            InvalidDFA::AcceptingSinkState => write!(f, "Sink state is accepting"),
+            InvalidDFA::NonAbsorbingSinkState => write!(f, "Sink state leads to another state"),
+            InvalidDFA::UnexpectedEndOfInput => write!(f, "Unexpected end of input"),
+            InvalidDFA::TrailingBytes => write!(f, "Trailing bytes after the DFA"),
+            InvalidDFA::InvalidDistanceTag(tag) => write!(f, "Invalid distance tag {}", tag),
+        }
+    }
+}
+
+impl Error for InvalidDFA {}
+
+fn invalid_data(err: InvalidDFA) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
 }
 
-#[cfg(feature = "fst_automaton")]
-use fst;
 #[cfg(feature = "fst_automaton")]
 impl fst::Automaton for DFA {
     type State = u32;
@@ -94,7 +1370,7 @@ impl fst::Automaton for DFA {
 
     fn is_match(&self, state: &u32) -> bool {
         match self.distance(*state) {
-            Distance::Exact(d) => true,
+            Distance::Exact(_) => true,
             Distance::AtLeast(_) => false,
         }
     }
@@ -108,6 +1384,191 @@ impl fst::Automaton for DFA {
     }
 }
 
+// regex-automata delays matches by one byte: a state is a match state
+// iff the state preceding the last transition is accepting.
+// Each of its state ids therefore encodes a state of the `DFA` together with
+// whether the previous state was accepting, as `2 * state + previous_is_match`,
+// so that the dead state `(SINK_STATE, false)` is `0`.
+#[cfg(feature = "regex_automata")]
+impl DFA {
+    fn regex_automata_state(&self, state: u32, previous_is_match: bool) -> StateID {
+        StateID::must(2 * state as usize + previous_is_match as usize)
+    }
+
+    fn is_accepting(&self, state: u32) -> bool {
+        matches!(self.distance(state), Distance::Exact(_))
+    }
+}
+
+/// The `DFA` matches the prefixes of the haystack, starting at the beginning
+/// of the search, that are within its maximum distance of the query.
+/// The search is always anchored, and the state ids are not those of the `DFA`.
+//
+// SAFETY:
+// - `start_state` and `next_state` return the id `2 * state + previous_is_match` of
+//   the initial state, of the sink state, or of the destination of a transition,
+//   all of which exist in a valid `DFA` (see `DFA::validate()`). `StateID::must`
+//   panics rather than returning an id that does not fit in a `StateID`.
+// - The state ids are only interpreted by the methods of this impl, which index the
+//   transition table with bounds checks, including `next_state_unchecked`. An invalid
+//   id therefore results in a panic, never in an out of bounds access.
+// - `is_dead_state` only holds for the sink state reached without a pending match.
+//   The sink state is never accepting and only leads to itself, so that no match can
+//   be reported past it.
+// - `is_match_state` holds iff the previous state was accepting, as the matches are
+//   delayed by one byte, and `is_special_state` holds iff the state is dead or a match
+//   state, as there are no quit, start or accelerated states.
+// - `pattern_len`, `match_len` and `match_pattern` describe the single pattern of
+//   the `DFA`, and `start_state` never returns the state of any other pattern.
+// - `is_utf8` is false, as the `DFA` also matches byte sequences that are not
+//   valid UTF-8.
+#[cfg(feature = "regex_automata")]
+unsafe impl regex_automata::dfa::Automaton for DFA {
+    fn next_state(&self, current: StateID, input: u8) -> StateID {
+        let state = (current.as_usize() / 2) as u32;
+        self.regex_automata_state(self.transition(state, input), self.is_accepting(state))
+    }
+
+    unsafe fn next_state_unchecked(&self, current: StateID, input: u8) -> StateID {
+        // `next_state` checks the bounds of `current`, so that this is sound for any id.
+        self.next_state(current, input)
+    }
+
+    fn next_eoi_state(&self, current: StateID) -> StateID {
+        let state = (current.as_usize() / 2) as u32;
+        self.regex_automata_state(SINK_STATE, self.is_accepting(state))
+    }
+
+    fn start_state(&self, config: &start::Config) -> Result<StateID, StartError> {
+        match config.get_anchored() {
+            Anchored::Pattern(pattern_id) if pattern_id != PatternID::ZERO => {
+                Ok(self.regex_automata_state(SINK_STATE, false))
+            }
+            _ => Ok(self.regex_automata_state(self.initial_state, false)),
+        }
+    }
+
+    fn is_special_state(&self, id: StateID) -> bool {
+        self.is_dead_state(id) || self.is_match_state(id)
+    }
+
+    fn is_dead_state(&self, id: StateID) -> bool {
+        id == StateID::ZERO
+    }
+
+    fn is_quit_state(&self, _id: StateID) -> bool {
+        false
+    }
+
+    fn is_match_state(&self, id: StateID) -> bool {
+        id.as_usize() % 2 == 1
+    }
+
+    fn is_start_state(&self, _id: StateID) -> bool {
+        false
+    }
+
+    fn is_accel_state(&self, _id: StateID) -> bool {
+        false
+    }
+
+    fn pattern_len(&self) -> usize {
+        1
+    }
+
+    fn match_len(&self, _id: StateID) -> usize {
+        1
+    }
+
+    fn match_pattern(&self, _id: StateID, _index: usize) -> PatternID {
+        PatternID::ZERO
+    }
+
+    fn has_empty(&self) -> bool {
+        self.is_accepting(self.initial_state)
+    }
+
+    fn is_utf8(&self) -> bool {
+        false
+    }
+
+    fn is_always_start_anchored(&self) -> bool {
+        true
+    }
+}
+
+/// Builder to assemble a custom [DFA](./struct.DFA.html) state by state.
+///
+/// States are identified by the id returned by `add_state`, starting at `0`.
+/// All of the transitions of a new state lead to [SINK_STATE](./constant.SINK_STATE.html)
+/// until they are set explicitly, so that the first state added is
+/// expected to be the sink state.
+///
+/// ```rust
+/// # use levenshtein_automata::{DFABuilder, Distance, SINK_STATE};
+/// let mut dfa_builder = DFABuilder::default();
+/// let sink_state = dfa_builder.add_state(Distance::AtLeast(1));
+/// assert_eq!(sink_state, SINK_STATE);
+/// let initial_state = dfa_builder.add_state(Distance::AtLeast(1));
+/// let accepting_state = dfa_builder.add_state(Distance::Exact(0));
+/// dfa_builder.set_transition(initial_state, b'a', accepting_state);
+/// dfa_builder.set_initial_state(initial_state);
+/// let dfa = dfa_builder.build().unwrap();
+/// assert_eq!(dfa.eval("a"), Distance::Exact(0));
+/// assert_eq!(dfa.eval("ab"), Distance::AtLeast(1));
+/// ```
+#[derive(Default)]
+pub struct DFABuilder {
+    transitions: Vec<[u32; 256]>,
+    distances: Vec<Distance>,
+    initial_state: u32,
+}
+
+impl DFABuilder {
+    /// Adds a new state and returns its id.
+    pub fn add_state(&mut self, distance: Distance) -> u32 {
+        let state = self.distances.len() as u32;
+        self.transitions.push([SINK_STATE; 256]);
+        self.distances.push(distance);
+        state
+    }
+
+    /// Defines the destination state reached from `from` after consuming `byte`.
+    pub fn set_transition(&mut self, from: u32, byte: u8, to: u32) {
+        assert!(
+            (to as usize) < self.distances.len(),
+            "Destination state has not been added"
+        );
+        self.transitions[from as usize][byte as usize] = to;
+    }
+
+    /// Defines the initial state of the `DFA`. It defaults to `0`.
+    pub fn set_initial_state(&mut self, state: u32) {
+        assert!(
+            (state as usize) < self.distances.len(),
+            "Initial state has not been added"
+        );
+        self.initial_state = state;
+    }
+
+    /// Builds the `DFA`.
+    ///
+    /// Returns an error if the `DFA` is not well-formed, for instance if no
+    /// state was added or if the sink state is accepting.
+    /// See [DFA::validate(...)](./struct.DFA.html#method.validate).
+    pub fn build(self) -> Result<DFA, InvalidDFA> {
+        let dfa = DFA {
+            transitions: self.transitions,
+            distances: self.distances,
+            initial_state: self.initial_state,
+            parametric_states: Vec::new(),
+            alignment_query: None,
+        };
+        dfa.validate()?;
+        Ok(dfa)
+    }
+}
+
 fn fill(dest: &mut [u32], val: u32) {
     for d in dest {
         *d = val;
@@ -133,7 +1594,7 @@ impl<'a> Utf8DFAStateBuilder<'a> {
         let bytes: &[u8] = chr.encode_utf8(&mut buffer).as_bytes();
         let mut from_state_id_decoded = self.state_id;
         for (i, b) in bytes[..bytes.len() - 1].iter().cloned().enumerate() {
-            let remaining_num_bytes = bytes.len() - i as usize - 1 as usize;
+            let remaining_num_bytes = bytes.len() - i - 1;
             let default_successor = self.default_successor[remaining_num_bytes];
             let mut intermediary_state_id: u32 =
                 self.dfa_builder.transitions[from_state_id_decoded as usize][b as usize];
@@ -162,13 +1623,24 @@ impl<'a> Utf8DFAStateBuilder<'a> {
 /// `Utf8DFABuilder` makes it possible to define a DFA
 /// that takes unicode character, and build a `DFA`
 /// that operates on utf-8 encoded `&[u8]`.
+///
+/// It is the [DFACompiler](./trait.DFACompiler.html) used by
+/// [ParametricDFA::build_dfa(...)](./struct.ParametricDFA.html#method.build_dfa).
+/// A custom `DFACompiler` can wrap it to reuse the expansion of the char
+/// transitions into chains of byte transitions. Its `add_char_transition`
+/// panics if `from` is not the last state begun.
 pub struct Utf8DFABuilder {
     index: Vec<Option<u32>>,
     distances: Vec<Distance>,
     transitions: Vec<[u32; 256]>,
+    original_states: Vec<Option<u32>>,
+    parametric_states: Vec<ParametricState>,
     initial_state: u32,
     num_states: u32,
     max_num_states: u32,
+    // The last state defined with `add_state`: its original id, its id in the
+    // `DFA` and the chain of predecessors of its default successor.
+    current_state: Option<(u32, u32, [u32; 4])>,
 }
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy)]
@@ -181,21 +1653,37 @@ impl Utf8StateId {
     pub fn predecessor(state_id: u32, num_steps: u8) -> Utf8StateId {
         Utf8StateId(state_id * 4u32 + u32::from(num_steps))
     }
+
+    /// Returns the original state id, unless the state is one
+    /// of its predecessors.
+    pub fn original_state(self) -> Option<u32> {
+        let state_id = self.0 / 4u32;
+        if Utf8StateId::original(state_id) == self {
+            Some(state_id)
+        } else {
+            None
+        }
+    }
 }
 
 impl Utf8DFABuilder {
     /// Creates a new dictionary.
     ///
     /// The `builder` will only accept `state_id` that are
-    /// lower than `max_num_states`.
+    /// lower than `max_num_states`. When used as the `DFACompiler` of
+    /// [ParametricDFA::build_dfa_with_compiler(...)](./struct.ParametricDFA.html#method.build_dfa_with_compiler),
+    /// `max_num_states` must be at least `num_states() * (query_len + 1)`.
     pub fn with_max_num_states(max_num_states: usize) -> Utf8DFABuilder {
         Utf8DFABuilder {
             index: vec![None; max_num_states * 4 + 3],
             distances: Vec::with_capacity(100),
             transitions: Vec::with_capacity(100),
+            original_states: Vec::with_capacity(100),
+            parametric_states: Vec::new(),
             initial_state: 0u32,
             num_states: 0u32,
             max_num_states: max_num_states as u32,
+            current_state: None,
         }
     }
 
@@ -205,6 +1693,7 @@ impl Utf8DFABuilder {
         self.distances
             .resize(new_state as usize + 1, Distance::AtLeast(255));
         self.transitions.resize(new_state as usize + 1, [0u32; 256]);
+        self.original_states.resize(new_state as usize + 1, None);
         new_state
     }
 
@@ -215,21 +1704,24 @@ impl Utf8DFABuilder {
         }
         let new_state = self.allocate();
         self.index[state_bucket] = Some(new_state);
+        if let Some(original_state) = state.original_state() {
+            self.original_states[new_state as usize] = Some(original_state);
+        }
         new_state
     }
 
-    pub fn set_initial_state(&mut self, initial_state: u32) {
+    pub(crate) fn set_initial_state(&mut self, initial_state: u32) {
         let state_id_decoded = self.get_or_allocate(Utf8StateId::original(initial_state));
         self.initial_state = state_id_decoded
     }
 
     /// Define a new state.
-    pub fn add_state(
+    pub(crate) fn add_state(
         &mut self,
         state: u32,
         distance: Distance,
         default_successor_orig: u32,
-    ) -> Utf8DFAStateBuilder {
+    ) -> Utf8DFAStateBuilder<'_> {
         assert!(
             state < self.max_num_states,
             "State id is larger than max_num_states"
@@ -268,6 +1760,7 @@ impl Utf8DFABuilder {
             fill(&mut transitions[240..256], predecessor_states[3]);
         }
 
+        self.current_state = Some((state, state_id, predecessor_states));
         Utf8DFAStateBuilder {
             dfa_builder: self,
             state_id,
@@ -275,19 +1768,139 @@ impl Utf8DFABuilder {
         }
     }
 
-    pub fn build(self) -> DFA {
+    /// Builds the `DFA`, keeping track of the parametric state each state
+    /// originates from if they were given with `set_parametric_states`.
+    pub(crate) fn build(mut self) -> DFA {
+        let parametric_states = if self.parametric_states.is_empty() {
+            Vec::new()
+        } else {
+            let states = &self.parametric_states;
+            self.original_states
+                .iter()
+                .map(|original_state| {
+                    original_state.map(|original_state| states[original_state as usize])
+                })
+                .collect()
+        };
+        self.make_sink_absorbing();
         DFA {
             transitions: self.transitions,
             distances: self.distances,
             initial_state: self.initial_state,
+            parametric_states,
+            alignment_query: None,
         }
     }
+
+    // The bytes starting a multibyte char lead from the sink state to the chain of
+    // predecessors of its default successor rather than to the sink itself.
+    // If no accepting state can be reached from the sink, all of its
+    // transitions are redirected to itself, so that the sink is absorbing.
+    fn make_sink_absorbing(&mut self) {
+        if self.transitions.is_empty() {
+            return;
+        }
+        let mut visited = vec![false; self.transitions.len()];
+        let mut stack = vec![SINK_STATE];
+        visited[SINK_STATE as usize] = true;
+        while let Some(state) = stack.pop() {
+            if let Distance::Exact(_) = self.distances[state as usize] {
+                return;
+            }
+            for &dest in self.transitions[state as usize].iter() {
+                if !visited[dest as usize] {
+                    visited[dest as usize] = true;
+                    stack.push(dest);
+                }
+            }
+        }
+        fill(&mut self.transitions[SINK_STATE as usize], SINK_STATE);
+    }
+}
+
+/// Backend receiving the states and transitions computed by
+/// [ParametricDFA::build_dfa_with_compiler(...)](./struct.ParametricDFA.html#method.build_dfa_with_compiler).
+///
+/// States are identified by ids lower than `num_states() * (query_len + 1)`,
+/// where `num_states()` is the number of states of the `ParametricDFA`.
+/// The state `0` is the sink state.
+/// The transitions of a state are all added right after the state is begun.
+///
+/// The transitions are labelled with chars. [Utf8DFABuilder](./struct.Utf8DFABuilder.html)
+/// expands them into byte transitions, and can be wrapped by a custom `DFACompiler`.
+pub trait DFACompiler {
+    /// The result of the compilation, for instance a [DFA](./struct.DFA.html).
+    type Output;
+
+    /// Defines a new state, with its distance and the state reached
+    /// after consuming a char that does not appear in the query.
+    fn begin_state(&mut self, state_id: u32, distance: Distance, default_successor: u32);
+
+    /// Adds the transition from the state `from` consuming the char `c`.
+    ///
+    /// `from` is always the last state defined with
+    /// [begin_state(...)](#tymethod.begin_state): all of the transitions of
+    /// a state are added right after it is begun, and before the next state is begun.
+    fn add_char_transition(&mut self, from: u32, c: char, to: u32);
+
+    /// Sets the initial state.
+    fn set_initial(&mut self, state: u32);
+
+    /// Receives the parametric state associated to each state id, once all
+    /// of the states have been defined: `parametric_states[state_id]` is the
+    /// parametric state of `state_id`.
+    ///
+    /// Does nothing by default.
+    fn set_parametric_states(&mut self, parametric_states: Vec<ParametricState>) {
+        let _ = parametric_states;
+    }
+
+    /// Returns the result of the compilation.
+    fn finish(self) -> Self::Output;
+}
+
+impl DFACompiler for Utf8DFABuilder {
+    type Output = DFA;
+
+    fn begin_state(&mut self, state_id: u32, distance: Distance, default_successor: u32) {
+        self.add_state(state_id, distance, default_successor);
+    }
+
+    fn add_char_transition(&mut self, from: u32, c: char, to: u32) {
+        let (current_state, state_id, default_successor) = self
+            .current_state
+            .expect("Transitions must be added after their state is begun");
+        assert_eq!(
+            current_state, from,
+            "Transitions must be added right after their state is begun"
+        );
+        Utf8DFAStateBuilder {
+            dfa_builder: self,
+            state_id,
+            default_successor,
+        }
+        .add_transition(c, to);
+    }
+
+    fn set_initial(&mut self, state: u32) {
+        self.set_initial_state(state);
+    }
+
+    fn set_parametric_states(&mut self, parametric_states: Vec<ParametricState>) {
+        self.parametric_states = parametric_states;
+    }
+
+    fn finish(self) -> DFA {
+        self.build()
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use super::DFABuilder;
     use super::Distance;
+    use super::InvalidDFA;
     use super::Utf8DFABuilder;
 
     #[test]
@@ -297,7 +1910,7 @@ mod tests {
         dfa_builder.add_state(1, Distance::Exact(0u8), 0);
         dfa_builder.set_initial_state(1u32);
         let dfa = dfa_builder.build();
-        let parity_num_letters = |s: &str| dfa.eval(s).to_u8();
+        let parity_num_letters = |s: &str| dfa.eval(s).distance_value();
         assert_eq!(parity_num_letters("abcdef"), 0u8);
         assert_eq!(parity_num_letters("a"), 1u8);
         assert_eq!(parity_num_letters("aあ"), 0u8);
@@ -307,4 +1920,78 @@ mod tests {
         assert_eq!(parity_num_letters("あ"), 1u8);
         assert_eq!(parity_num_letters("ああ"), 0u8);
     }
+
+    #[test]
+    fn test_is_empty_language() {
+        let mut dfa_builder = Utf8DFABuilder::with_max_num_states(2);
+        dfa_builder.add_state(0, Distance::AtLeast(1u8), 1);
+        dfa_builder.add_state(1, Distance::AtLeast(1u8), 0);
+        dfa_builder.set_initial_state(1u32);
+        assert!(dfa_builder.build().is_empty_language());
+
+        let mut dfa_builder = Utf8DFABuilder::with_max_num_states(2);
+        dfa_builder.add_state(0, Distance::AtLeast(1u8), 1);
+        dfa_builder.add_state(1, Distance::Exact(0u8), 0);
+        dfa_builder.set_initial_state(1u32);
+        assert!(!dfa_builder.build().is_empty_language());
+    }
+
+    #[test]
+    fn test_state_name() {
+        let mut dfa_builder = Utf8DFABuilder::with_max_num_states(4);
+        dfa_builder.add_state(0, Distance::AtLeast(2u8), 0);
+        dfa_builder.add_state(1, Distance::Exact(1u8), 2);
+        dfa_builder.add_state(2, Distance::Exact(1u8), 3);
+        dfa_builder.add_state(3, Distance::Exact(1u8), 0);
+        dfa_builder.set_initial_state(1u32);
+        let dfa = dfa_builder.build();
+        let mut state = dfa.initial_state();
+        assert_eq!(dfa.state_name(state), "INIT");
+        state = dfa.transition(state, b'a');
+        assert_eq!(dfa.state_name(state), "d=1[1]");
+        state = dfa.transition(state, b'a');
+        assert_eq!(dfa.state_name(state), "d=1[2]");
+        state = dfa.transition(state, b'a');
+        assert_eq!(dfa.state_name(state), "SINK");
+    }
+
+    #[test]
+    fn test_dfa_builder_invalid() {
+        assert_eq!(
+            DFABuilder::default().build().err(),
+            Some(InvalidDFA::NoStates)
+        );
+        let mut dfa_builder = DFABuilder::default();
+        dfa_builder.add_state(Distance::Exact(0u8));
+        assert_eq!(
+            dfa_builder.build().err(),
+            Some(InvalidDFA::AcceptingSinkState)
+        );
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        let mut dfa_builder = DFABuilder::default();
+        let sink = dfa_builder.add_state(Distance::AtLeast(1u8));
+        let initial = dfa_builder.add_state(Distance::Exact(1u8));
+        let accepting = dfa_builder.add_state(Distance::Exact(0u8));
+        for b in b'a'..=b'z' {
+            dfa_builder.set_transition(initial, b, accepting);
+        }
+        dfa_builder.set_transition(initial, b'0', sink);
+        dfa_builder.set_initial_state(initial);
+        let dfa = dfa_builder.build().unwrap();
+        let mut output = Vec::new();
+        dfa.pretty_print(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "state 0: AtLeast(1) (sink)\n  \
+             [everything else] -> 0\n\
+             state 1: Exact(1) (initial)\n  \
+             [a-z] -> 2\n  \
+             [everything else] -> 0\n\
+             state 2: Exact(0)\n  \
+             [everything else] -> 0\n"
+        );
+    }
 }
@@ -0,0 +1,61 @@
+use super::{Distance, DFA};
+
+/// Computes the distance to the query of a [DFA](./struct.DFA.html)
+/// incrementally, as the text is fed to it.
+///
+/// ```rust
+/// # extern crate levenshtein_automata;
+/// use levenshtein_automata::{Distance, DynamicLevenshteinDistance, LevenshteinAutomatonBuilder};
+/// # fn main() {
+/// let dfa = LevenshteinAutomatonBuilder::new(1, false).build_dfa("abc");
+/// let mut distance = DynamicLevenshteinDistance::new(dfa);
+/// assert_eq!(distance.feed_str("ab"), Distance::Exact(1));
+/// assert_eq!(distance.feed(b'c'), Distance::Exact(0));
+/// distance.reset();
+/// assert_eq!(distance.distance(), Distance::AtLeast(2));
+/// # }
+/// ```
+pub struct DynamicLevenshteinDistance {
+    dfa: DFA,
+    state: u32,
+}
+
+impl DynamicLevenshteinDistance {
+    /// Creates a `DynamicLevenshteinDistance`, starting from the
+    /// initial state of the `dfa`.
+    pub fn new(dfa: DFA) -> DynamicLevenshteinDistance {
+        let state = dfa.initial_state();
+        DynamicLevenshteinDistance { dfa, state }
+    }
+
+    /// Consumes one byte of the text, and returns the distance
+    /// of the text consumed so far.
+    pub fn feed(&mut self, b: u8) -> Distance {
+        self.state = self.dfa.transition(self.state, b);
+        self.distance()
+    }
+
+    /// Consumes a string slice, and returns the distance
+    /// of the text consumed so far.
+    pub fn feed_str(&mut self, s: &str) -> Distance {
+        for &b in s.as_bytes() {
+            self.state = self.dfa.transition(self.state, b);
+        }
+        self.distance()
+    }
+
+    /// Returns the distance of the text consumed so far.
+    pub fn distance(&self) -> Distance {
+        self.dfa.distance(self.state)
+    }
+
+    /// Goes back to the initial state, as if no text had been consumed.
+    pub fn reset(&mut self) {
+        self.state = self.dfa.initial_state();
+    }
+
+    /// Returns the wrapped `DFA`.
+    pub fn into_dfa(self) -> DFA {
+        self.dfa
+    }
+}
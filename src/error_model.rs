@@ -0,0 +1,152 @@
+use super::dfa::{Utf8DFABuilder, DFA};
+use super::Distance;
+use super::Index;
+
+/// Costs of the edit operations used to compute a weighted
+/// Levenshtein distance.
+///
+/// A cost of `None` means that the operation is forbidden.
+///
+/// See [ParametricDFA::build_dfa_with_error_model(...)](./struct.ParametricDFA.html#method.build_dfa_with_error_model).
+pub trait ErrorModel {
+    /// Cost of substituting the char `from` of the query
+    /// by the char `to` of the text.
+    ///
+    /// This is never called with `from == to`: matching a char is free.
+    fn cost(&self, from: char, to: char) -> Option<u8>;
+
+    /// Cost of inserting the char `c` of the text.
+    fn cost_insert(&self, c: char) -> Option<u8>;
+
+    /// Cost of deleting the char `c` of the query.
+    fn cost_delete(&self, c: char) -> Option<u8>;
+
+    /// Chars, other than the chars of the query, for which
+    /// the costs may be specific.
+    ///
+    /// All of the other chars of the text are assumed to share the same costs.
+    /// These costs are computed once, using one arbitrary char that neither
+    /// belongs to the query nor to `alphabet()`.
+    fn alphabet(&self) -> Vec<char> {
+        Vec::new()
+    }
+}
+
+/// Error model in which all of the edit operations cost 1.
+///
+/// It yields the same distances as the regular Levenshtein distance,
+/// without transpositions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnitErrorModel;
+
+impl ErrorModel for UnitErrorModel {
+    fn cost(&self, _from: char, _to: char) -> Option<u8> {
+        Some(1)
+    }
+
+    fn cost_insert(&self, _c: char) -> Option<u8> {
+        Some(1)
+    }
+
+    fn cost_delete(&self, _c: char) -> Option<u8> {
+        Some(1)
+    }
+}
+
+fn add_cost(distance: u8, cost: Option<u8>, limit: u8) -> u8 {
+    cost.map(|cost| distance.saturating_add(cost).min(limit))
+        .unwrap_or(limit)
+}
+
+struct StateTransitions {
+    distance: Distance,
+    default_successor: u32,
+    transitions: Vec<(char, u32)>,
+}
+
+/// Builds a `DFA` computing the weighted Levenshtein distance to the `query`.
+///
+/// Each state of the `DFA` is a row of the Wagner-Fischer matrix,
+/// with the distances capped to `max_distance + 1`.
+pub(crate) fn build_dfa(query: &str, max_distance: u8, model: &dyn ErrorModel) -> DFA {
+    let query_chars: Vec<char> = query.chars().collect();
+    let limit = max_distance.saturating_add(1);
+
+    let mut alphabet = query_chars.clone();
+    alphabet.extend(model.alphabet());
+    alphabet.sort();
+    alphabet.dedup();
+    let default_char = (0..=u32::from(char::MAX))
+        .rev()
+        .filter_map(std::char::from_u32)
+        .find(|c| alphabet.binary_search(c).is_err())
+        .expect("The alphabet cannot contain all of the chars");
+
+    let transition = |row: &[u8], c: char| -> Vec<u8> {
+        let mut dest = Vec::with_capacity(row.len());
+        dest.push(add_cost(row[0], model.cost_insert(c), limit));
+        for (i, &query_char) in query_chars.iter().enumerate() {
+            let substitution_cost = if query_char == c {
+                Some(0)
+            } else {
+                model.cost(query_char, c)
+            };
+            let d = add_cost(row[i], substitution_cost, limit)
+                .min(add_cost(row[i + 1], model.cost_insert(c), limit))
+                .min(add_cost(dest[i], model.cost_delete(query_char), limit));
+            dest.push(d);
+        }
+        dest
+    };
+
+    let sink_row = vec![limit; query_chars.len() + 1];
+    let mut initial_row = vec![0u8];
+    for &query_char in &query_chars {
+        let d = add_cost(
+            initial_row[initial_row.len() - 1],
+            model.cost_delete(query_char),
+            limit,
+        );
+        initial_row.push(d);
+    }
+
+    let mut index: Index<Vec<u8>> = Index::new();
+    assert_eq!(index.get_or_allocate(&sink_row), 0);
+    let initial_state = index.get_or_allocate(&initial_row);
+
+    let mut states: Vec<StateTransitions> = Vec::new();
+    for state_id in 0u32.. {
+        if state_id == index.len() {
+            break;
+        }
+        let row = index.get_from_id(state_id).clone();
+        let last = row[row.len() - 1];
+        let distance = if last < limit {
+            Distance::Exact(last)
+        } else {
+            Distance::AtLeast(limit)
+        };
+        let default_successor = index.get_or_allocate(&transition(&row, default_char));
+        let transitions: Vec<(char, u32)> = alphabet
+            .iter()
+            .map(|&c| (c, index.get_or_allocate(&transition(&row, c))))
+            .filter(|&(_, dest)| dest != default_successor)
+            .collect();
+        states.push(StateTransitions {
+            distance,
+            default_successor,
+            transitions,
+        });
+    }
+
+    let mut dfa_builder = Utf8DFABuilder::with_max_num_states(states.len());
+    for (state_id, state) in states.into_iter().enumerate() {
+        let mut state_builder =
+            dfa_builder.add_state(state_id as u32, state.distance, state.default_successor);
+        for (c, dest) in state.transitions {
+            state_builder.add_transition(c, dest);
+        }
+    }
+    dfa_builder.set_initial_state(initial_state);
+    dfa_builder.build()
+}
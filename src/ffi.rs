@@ -0,0 +1,86 @@
+//! C API, enabled by the `c_api` feature.
+//!
+//! The header `include/levenshtein_automata.h` is generated
+//! by [cbindgen](https://github.com/mozilla/cbindgen):
+//!
+//! ```ignore
+//! cbindgen --config cbindgen.toml --output include/levenshtein_automata.h
+//! ```
+
+use super::{Distance, ParametricDFA, DFA};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+/// Bit set in the result of `lev_dfa_eval` when the distance is not exact.
+pub const LEV_DFA_NOT_EXACT: u8 = 0x80;
+
+/// Builds the DFA computing the Levenshtein distance to `query`.
+///
+/// Returns a null pointer if `query` is null or is not valid UTF-8,
+/// or if `max_distance` is not in `1..=4`.
+/// The DFA must be released with `lev_dfa_free`.
+///
+/// # Safety
+///
+/// `query` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lev_dfa_build(
+    query: *const c_char,
+    max_distance: u8,
+    transposition: bool,
+) -> *mut DFA {
+    if query.is_null() {
+        return ptr::null_mut();
+    }
+    let query = match CStr::from_ptr(query).to_str() {
+        Ok(query) => query,
+        Err(_) => return ptr::null_mut(),
+    };
+    let parametric_dfa = match ParametricDFA::standard(max_distance, transposition) {
+        Some(parametric_dfa) => parametric_dfa,
+        None => return ptr::null_mut(),
+    };
+    Box::into_raw(Box::new(parametric_dfa.build_dfa(query, false)))
+}
+
+/// Returns the distance between the query of the `dfa` and the `len` bytes of `text`.
+///
+/// If the distance is not exact, i.e. larger than the max distance of the DFA,
+/// the `LEV_DFA_NOT_EXACT` bit is set. See `lev_dfa_is_exact`.
+///
+/// # Safety
+///
+/// `dfa` must have been returned by `lev_dfa_build` and not freed,
+/// and `text` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn lev_dfa_eval(dfa: *const DFA, text: *const c_char, len: usize) -> u8 {
+    let text: &[u8] = if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(text as *const u8, len)
+    };
+    match (*dfa).eval(text) {
+        Distance::Exact(d) => d,
+        Distance::AtLeast(d) => d | LEV_DFA_NOT_EXACT,
+    }
+}
+
+/// Returns true iff the result of `lev_dfa_eval` is an exact distance.
+#[no_mangle]
+pub extern "C" fn lev_dfa_is_exact(result: u8) -> bool {
+    result & LEV_DFA_NOT_EXACT == 0
+}
+
+/// Releases a DFA returned by `lev_dfa_build`. Does nothing if `dfa` is null.
+///
+/// # Safety
+///
+/// `dfa` must be null or have been returned by `lev_dfa_build`, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn lev_dfa_free(dfa: *mut DFA) {
+    if !dfa.is_null() {
+        drop(Box::from_raw(dfa));
+    }
+}
@@ -21,7 +21,7 @@ impl<I: Eq + Hash + Clone + Debug> Index<I> {
         if item_index == index_len {
             self.items.push(item.clone());
         }
-        item_index as u32
+        item_index
     }
 
     pub fn len(&self) -> u32 {
@@ -31,4 +31,14 @@ impl<I: Eq + Hash + Clone + Debug> Index<I> {
     pub fn get_from_id(&self, id: u32) -> &I {
         &self.items[id as usize]
     }
+
+    /// Returns the items of the index, ordered by id.
+    pub fn values(&self) -> impl Iterator<Item = &I> + '_ {
+        self.items.iter()
+    }
+
+    /// Returns the ids of the items of the index.
+    pub fn ids(&self) -> impl Iterator<Item = u32> {
+        0..self.len()
+    }
 }
@@ -1,16 +1,19 @@
 use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
 
-#[cfg(test)]
-pub fn compute_characteristic_vector(query: &[char], c: char) -> u64 {
+pub(crate) fn compute_characteristic_vector(query: &[char], c: char) -> u64 {
     let mut chi = 0u64;
-    for i in 0..query.len() {
-        if query[i] == c {
+    for (i, &chr) in query.iter().enumerate() {
+        if chr == c {
             chi |= 1u64 << i;
         }
     }
     chi
 }
 
+/// Set of the states a [LevenshteinNFA](./struct.LevenshteinNFA.html) can be in.
 #[derive(Debug, Hash, Eq, PartialEq, Clone)]
 pub struct MultiState {
     states: Vec<NFAState>,
@@ -43,15 +46,20 @@ impl MultiState {
         min_offset
     }
 
-    fn add_state(&mut self, new_state: NFAState) {
-        if self.states.iter().any(|state| state.imply(new_state)) {
+    /// Adds a state to the multistate.
+    ///
+    /// The states implied by `new_state` are removed. Returns `false`,
+    /// and leaves the multistate unchanged, if `new_state` is already
+    /// implied by one of its states.
+    pub fn add(&mut self, new_state: NFAState) -> bool {
+        if self.states.iter().any(|state| state.dominates(new_state)) {
             // this state is already included in the current set of states.
-            return;
+            return false;
         }
 
         let mut i = 0;
         while i < self.states.len() {
-            if new_state.imply(self.states[i]) {
+            if new_state.dominates(self.states[i]) {
                 self.states.swap_remove(i);
             } else {
                 i += 1;
@@ -59,6 +67,33 @@ impl MultiState {
         }
 
         self.states.push(new_state);
+        true
+    }
+
+    /// Removes the states that are implied by another state of the multistate.
+    pub fn remove_dominated(&mut self) {
+        let states = std::mem::take(&mut self.states);
+        for state in states {
+            self.add(state);
+        }
+    }
+}
+
+impl IntoIterator for MultiState {
+    type Item = NFAState;
+    type IntoIter = std::vec::IntoIter<NFAState>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.states.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MultiState {
+    type Item = NFAState;
+    type IntoIter = std::iter::Cloned<std::slice::Iter<'a, NFAState>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.states.iter().cloned()
     }
 }
 
@@ -69,7 +104,12 @@ impl MultiState {
 ///
 /// Over this distance, the automaton will invariably
 /// return `Distance::AtLeast(max_distance + 1)`.
+///
+/// This enum is `#[non_exhaustive]`: new variants may be added
+/// in the future. Use [distance_value(...)](#method.distance_value)
+/// to get the highest lower bound of the distance whatever the variant.
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum Distance {
     Exact(u8),
     AtLeast(u8),
@@ -85,11 +125,92 @@ impl Distance {
     ///     Distance::AtLeast(d) => d,
     /// }
     /// ```
-    pub fn to_u8(&self) -> u8 {
+    pub fn distance_value(&self) -> u8 {
         match *self {
             Distance::Exact(d) | Distance::AtLeast(d) => d,
         }
     }
+
+    /// Returns the highest lower bound for the distance.
+    #[deprecated(since = "0.3.0", note = "Use `distance_value` instead")]
+    pub fn to_u8(&self) -> u8 {
+        self.distance_value()
+    }
+
+    /// Returns the distance to the closest of two strings, given
+    /// their respective distances.
+    ///
+    /// If the result cannot be known exactly, the highest lower bound is returned.
+    /// For instance, `Exact(2).min(AtLeast(1))` is `AtLeast(1)`.
+    pub fn min(self, other: Distance) -> Distance {
+        use self::Distance::*;
+        match (self, other) {
+            (Exact(left), Exact(right)) => Exact(left.min(right)),
+            (Exact(exact), AtLeast(at_least)) | (AtLeast(at_least), Exact(exact)) => {
+                if exact <= at_least {
+                    Exact(exact)
+                } else {
+                    AtLeast(at_least)
+                }
+            }
+            (AtLeast(left), AtLeast(right)) => AtLeast(left.min(right)),
+        }
+    }
+
+    /// Returns the distance to the farthest of two strings, given
+    /// their respective distances.
+    ///
+    /// If the result cannot be known exactly, the highest lower bound is returned.
+    /// For instance, `Exact(2).max(AtLeast(1))` is `AtLeast(2)`.
+    pub fn max(self, other: Distance) -> Distance {
+        use self::Distance::*;
+        match (self, other) {
+            (Exact(left), Exact(right)) => Exact(left.max(right)),
+            (Exact(left), AtLeast(right))
+            | (AtLeast(left), Exact(right))
+            | (AtLeast(left), AtLeast(right)) => AtLeast(left.max(right)),
+        }
+    }
+}
+
+impl From<Distance> for Option<u8> {
+    fn from(distance: Distance) -> Option<u8> {
+        match distance {
+            Distance::Exact(d) => Some(d),
+            Distance::AtLeast(_) => None,
+        }
+    }
+}
+
+impl From<u8> for Distance {
+    fn from(d: u8) -> Distance {
+        Distance::Exact(d)
+    }
+}
+
+/// Error returned when converting a `Distance::AtLeast` into a `u8`.
+///
+/// It holds the lower bound of the distance.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct DistanceNotExact(pub u8);
+
+impl fmt::Display for DistanceNotExact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Distance is not exact (at least {})", self.0)
+    }
+}
+
+impl Error for DistanceNotExact {}
+
+impl TryFrom<Distance> for u8 {
+    type Error = DistanceNotExact;
+
+    fn try_from(distance: Distance) -> Result<u8, DistanceNotExact> {
+        match distance {
+            Distance::Exact(d) => Ok(d),
+            Distance::AtLeast(d) => Err(DistanceNotExact(d)),
+        }
+    }
 }
 
 impl PartialOrd for Distance {
@@ -122,6 +243,9 @@ impl PartialOrd for Distance {
     }
 }
 
+/// Nondeterministic automaton computing the Levenshtein distance,
+/// from which a [ParametricDFA](./struct.ParametricDFA.html) is built.
+#[derive(Debug, Eq, PartialEq)]
 pub struct LevenshteinNFA {
     max_distance: u8,
     damerau: bool,
@@ -135,21 +259,39 @@ fn extract_bit(bitset: u64, pos: u8) -> bool {
 }
 
 fn dist(left: u32, right: u32) -> u32 {
-    if left > right {
-        left - right
-    } else {
-        right - left
-    }
+    left.abs_diff(right)
 }
 
 impl LevenshteinNFA {
     pub fn levenshtein(max_distance: u8, transposition: bool) -> LevenshteinNFA {
         LevenshteinNFA {
-            max_distance: max_distance,
+            max_distance,
             damerau: transposition,
         }
     }
 
+    /// Returns an upper bound on the number of states of the
+    /// [ParametricDFA](./struct.ParametricDFA.html) built from this NFA.
+    ///
+    /// This makes it possible to estimate the memory usage of
+    /// `ParametricDFA::from_nfa` before calling it.
+    /// For `max_distance <= 5`, the bound is exact. Beyond that,
+    /// no useful bound is known and `usize::MAX` is returned.
+    pub fn max_states_estimate(&self) -> usize {
+        // Number of states of the parametric DFAs, indexed by `max_distance`.
+        const NUM_STATES: [usize; 6] = [2, 6, 31, 197, 1_354, 9_714];
+        const NUM_STATES_DAMERAU: [usize; 6] = [2, 8, 68, 769, 9_628, 127_460];
+        let num_states: &[usize] = if self.damerau {
+            &NUM_STATES_DAMERAU
+        } else {
+            &NUM_STATES
+        };
+        num_states
+            .get(self.max_distance as usize)
+            .cloned()
+            .unwrap_or(usize::MAX)
+    }
+
     pub fn multistate_distance(&self, multistate: &MultiState, query_len: u32) -> Distance {
         multistate
             .states()
@@ -165,13 +307,18 @@ impl LevenshteinNFA {
         self.max_distance
     }
 
+    /// Returns true iff transpositions are assigned a distance of 1.
+    pub fn is_damerau(&self) -> bool {
+        self.damerau
+    }
+
     pub fn multistate_diameter(&self) -> u8 {
         2u8 * self.max_distance + 1u8
     }
 
     pub fn initial_states(&self) -> MultiState {
         let mut multistate = MultiState::empty();
-        multistate.add_state(NFAState::default());
+        multistate.add(NFAState::default());
         multistate
     }
 
@@ -196,14 +343,14 @@ impl LevenshteinNFA {
             // make mistakes.
 
             // insertion
-            multistate.add_state(NFAState {
+            multistate.add(NFAState {
                 offset: state.offset,
                 distance: state.distance + 1,
                 in_transpose: false,
             });
 
             // substitution
-            multistate.add_state(NFAState {
+            multistate.add(NFAState {
                 offset: state.offset + 1,
                 distance: state.distance + 1,
                 in_transpose: false,
@@ -212,7 +359,7 @@ impl LevenshteinNFA {
             for d in 1u8..self.max_distance + 1u8 - state.distance {
                 if extract_bit(symbol, d) {
                     // for d > 0, as many deletion and character match
-                    multistate.add_state(NFAState {
+                    multistate.add(NFAState {
                         offset: state.offset + 1 + u32::from(d),
                         distance: state.distance + d,
                         in_transpose: false,
@@ -221,7 +368,7 @@ impl LevenshteinNFA {
             }
 
             if self.damerau && extract_bit(symbol, 1) {
-                multistate.add_state(NFAState {
+                multistate.add(NFAState {
                     offset: state.offset,
                     distance: state.distance + 1,
                     in_transpose: true,
@@ -229,7 +376,7 @@ impl LevenshteinNFA {
             }
         }
         if extract_bit(symbol, 0) {
-            multistate.add_state(NFAState {
+            multistate.add(NFAState {
                 offset: state.offset + 1,
                 distance: state.distance,
                 in_transpose: false,
@@ -237,7 +384,7 @@ impl LevenshteinNFA {
         }
 
         if state.in_transpose && extract_bit(symbol, 0u8) {
-            multistate.add_state(NFAState {
+            multistate.add(NFAState {
                 offset: state.offset + 2,
                 distance: state.distance,
                 in_transpose: false,
@@ -261,6 +408,7 @@ impl LevenshteinNFA {
     }
 }
 
+/// State of a [LevenshteinNFA](./struct.LevenshteinNFA.html).
 #[derive(Default, Hash, Eq, PartialOrd, Ord, PartialEq, Copy, Clone, Debug)]
 pub struct NFAState {
     offset: u32,
@@ -269,13 +417,44 @@ pub struct NFAState {
 }
 
 impl NFAState {
-    fn imply(&self, other: NFAState) -> bool {
+    /// Creates a state of the NFA.
+    ///
+    /// * `offset` - number of chars of the query consumed.
+    /// * `distance` - number of edits applied.
+    /// * `in_transpose` - whether a transposition is in progress.
+    pub fn new(offset: u32, distance: u8, in_transpose: bool) -> NFAState {
+        NFAState {
+            offset,
+            distance,
+            in_transpose,
+        }
+    }
+
+    /// Returns the number of chars of the query consumed.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the number of edits applied.
+    pub fn distance(&self) -> u8 {
+        self.distance
+    }
+
+    /// Returns true iff a transposition is in progress.
+    pub fn in_transpose(&self) -> bool {
+        self.in_transpose
+    }
+
+    /// Returns true iff `self` dominates `other`.
+    ///
+    /// `self` dominates `other` if `other` is never needed to compute a distance
+    /// once `self` is in the multistate: whatever the remaining text, the distance
+    /// reached from `self` is at most as large as the distance reached from `other`.
+    /// This holds when `other.distance >= self.distance + |self.offset - other.offset|`,
+    /// the inequality being strict if `other` is in a transposition and `self` is not.
+    pub fn dominates(&self, other: NFAState) -> bool {
         let tranpose_imply = self.in_transpose | !other.in_transpose;
-        let delta_offset: u32 = if self.offset >= other.offset {
-            self.offset - other.offset
-        } else {
-            other.offset - self.offset
-        };
+        let delta_offset: u32 = self.offset.abs_diff(other.offset);
         if tranpose_imply {
             u32::from(other.distance) >= u32::from(self.distance) + delta_offset
         } else {
@@ -283,3 +462,53 @@ impl NFAState {
         }
     }
 }
+
+#[cfg(all(test, feature = "quickcheck"))]
+mod tests {
+    use super::{MultiState, NFAState};
+
+    fn multistate(states: &[(u8, u8, bool)]) -> MultiState {
+        let mut multistate = MultiState::empty();
+        for &(offset, distance, in_transpose) in states {
+            multistate.add(NFAState::new(
+                u32::from(offset),
+                distance % 8u8,
+                in_transpose,
+            ));
+        }
+        multistate
+    }
+
+    quickcheck::quickcheck! {
+        fn quickcheck_normalize_idempotent(states: Vec<(u8, u8, bool)>) -> bool {
+            let mut multistate = multistate(&states);
+            multistate.normalize();
+            let normalized = multistate.clone();
+            multistate.normalize() == 0 && multistate == normalized
+        }
+
+        fn quickcheck_normalize_min_offset(states: Vec<(u8, u8, bool)>) -> bool {
+            let mut multistate = multistate(&states);
+            let min_offset = multistate.states().iter().map(|state| state.offset).min();
+            let offset = multistate.normalize();
+            offset == min_offset.unwrap_or(0)
+                && multistate.states().iter().any(|state| state.offset == 0) == min_offset.is_some()
+        }
+
+        fn quickcheck_remove_dominated(states: Vec<(u8, u8, bool)>) -> bool {
+            let mut raw_multistate = MultiState {
+                states: states
+                    .iter()
+                    .map(|&(offset, distance, in_transpose)| {
+                        NFAState::new(u32::from(offset), distance % 8u8, in_transpose)
+                    })
+                    .collect(),
+            };
+            raw_multistate.remove_dominated();
+            raw_multistate.states.sort();
+            let mut expected = multistate(&states);
+            expected.states.sort();
+            raw_multistate == expected
+        }
+    }
+}
@@ -42,17 +42,40 @@ mod bench;
 #[cfg(test)]
 mod tests;
 
+mod alignment;
 mod alphabet;
+mod class_dfa;
+mod compact_dfa;
+mod compressed_dfa;
 mod dfa;
+mod dynamic_distance;
+mod error_model;
+#[cfg(feature = "c_api")]
+pub mod ffi;
 mod index;
 mod levenshtein_nfa;
 mod parametric_dfa;
-
-pub use self::dfa::{DFA, SINK_STATE};
+#[cfg(feature = "python")]
+mod python;
+mod trie;
+
+pub use self::alignment::{AlignKind, AlignOp, Alignment};
+pub use self::alphabet::{Alphabet, FullCharacteristicVector};
+pub use self::class_dfa::{ByteClassError, ClassDFA};
+pub use self::compact_dfa::{CompactDFA, OverflowError};
+pub use self::compressed_dfa::{CompressedDFA, CompressedTransitionRow};
+pub use self::dfa::{
+    DFABuilder, DFACompiler, DFAParts, InvalidDFA, OwnedDFA, StateIdType, TransitionStats,
+    Utf8DFABuilder, DFA, SINK_STATE,
+};
+pub use self::dynamic_distance::DynamicLevenshteinDistance;
+pub use self::error_model::{ErrorModel, UnitErrorModel};
 use self::index::Index;
-pub use self::levenshtein_nfa::Distance;
-use self::levenshtein_nfa::LevenshteinNFA;
-use self::parametric_dfa::ParametricDFA;
+pub use self::levenshtein_nfa::{Distance, DistanceNotExact, LevenshteinNFA, MultiState, NFAState};
+pub use self::parametric_dfa::{
+    compile_dfa_from_parametric, ParametricDFA, ParametricState, Transition,
+};
+pub use self::trie::{AcceptedIter, Trie};
 
 /// Builder for Levenshtein Automata.
 ///
@@ -60,6 +83,7 @@ use self::parametric_dfa::ParametricDFA;
 /// produce small (but not minimal) DFA.
 pub struct LevenshteinAutomatonBuilder {
     parametric_dfa: ParametricDFA,
+    transposition_cost_one: bool,
 }
 
 impl LevenshteinAutomatonBuilder {
@@ -76,7 +100,8 @@ impl LevenshteinAutomatonBuilder {
         let levenshtein_nfa = LevenshteinNFA::levenshtein(max_distance, transposition_cost_one);
         let parametric_dfa = ParametricDFA::from_nfa(&levenshtein_nfa);
         LevenshteinAutomatonBuilder {
-            parametric_dfa: parametric_dfa,
+            parametric_dfa,
+            transposition_cost_one,
         }
     }
 
@@ -94,13 +119,49 @@ impl LevenshteinAutomatonBuilder {
         self.parametric_dfa.build_dfa(query, false)
     }
 
+    /// Builds a Finite Determinstic Automaton to compute
+    /// the levenshtein distance to the full Unicode case folding of `query`.
+    ///
+    /// Unlike `.to_lowercase()`, case folding maps for instance `ß` to `ss`
+    /// and `ς` to `σ`.
+    ///
+    /// The strings evaluated by the resulting DFA are not case folded:
+    /// they must also be case folded before calling `eval`, for instance
+    /// using `caseless::default_case_fold_str(...)`.
+    ///
+    /// This method requires the `casefold` feature.
+    #[cfg(feature = "casefold")]
+    pub fn build_dfa_unicode_casefold(&self, query: &str) -> DFA {
+        let folded_query = caseless::default_case_fold_str(query);
+        self.build_dfa(&folded_query)
+    }
+
+    /// Builds a Finite Determinstic Automaton to compute
+    /// the levenshtein distance to the canonical decomposition (NFD) of `query`.
+    ///
+    /// The composed `é` (U+00E9) and the decomposed `e` followed by a combining
+    /// acute accent (U+0065 U+0301) result in the same DFA.
+    ///
+    /// The strings evaluated by the resulting DFA are not normalized:
+    /// they must also be in NFD before calling `eval`, for instance
+    /// using `unicode_normalization::UnicodeNormalization::nfd(...)`.
+    /// Note that a combining mark counts as a char of its own.
+    ///
+    /// This method requires the `unicode_normalization` feature.
+    #[cfg(feature = "unicode_normalization")]
+    pub fn build_dfa_unicode_decomposed(&self, query: &str) -> DFA {
+        use unicode_normalization::UnicodeNormalization;
+        let decomposed_query: String = query.nfd().collect();
+        self.build_dfa(&decomposed_query)
+    }
+
     /// Builds a Finite Determinstic Automaton that computes
     /// the prefix levenshtein distance to a given `query`.
     ///
     /// Given a test string, the resulting distance is defined as
     ///
     /// ```formula
-    ///     min( levenshtein(&test_string[..i], query } for i in 0..test_string.len() )
+    ///     min( levenshtein(&test_string[..i], query) for i in 0..=test_string.len() )
     /// ```
     ///
     /// Which translates as *the minimum distance of the prefixes of `test_strings`*.
@@ -109,4 +170,58 @@ impl LevenshteinAutomatonBuilder {
     pub fn build_prefix_dfa(&self, query: &str) -> DFA {
         self.parametric_dfa.build_dfa(query, true)
     }
+
+    /// Builds a Finite Determinstic Automaton that only accepts the strings
+    /// whose levenshtein distance to `query` is within `[lo, hi]`.
+    ///
+    /// This makes it possible, for instance, to exclude exact matches
+    /// by setting `lo` to `1`.
+    ///
+    /// The strings closer than `lo` to the `query` are rejected with
+    /// `Distance::AtLeast(0)`, which does not claim any bound on their distance.
+    /// The distance of the strings farther than `hi` is reported as
+    /// `Distance::AtLeast(hi + 1)`, except for the strings farther than
+    /// the `max_distance`, which keep their `Distance::AtLeast(max_distance + 1)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi` or if `hi` is larger than the `max_distance`
+    /// of the builder.
+    pub fn build_range_dfa(&self, query: &str, lo: u8, hi: u8) -> DFA {
+        self.parametric_dfa.build_range_dfa(query, lo, hi)
+    }
+
+    /// Builds a Finite Determinstic Automaton that computes
+    /// the suffix levenshtein distance to a given `query`.
+    ///
+    /// Given a test string, the resulting distance is defined as
+    ///
+    /// ```formula
+    ///     min( levenshtein(&test_string[i..], query) for i in 0..=test_string.len() )
+    /// ```
+    ///
+    /// Which translates as *the minimum distance of the suffixes of `test_strings`*.
+    ///
+    /// Unlike the DFAs returned by [.build_dfa(...)](./struct.LevenshteinAutomatonBuilder.html#method.build_dfa),
+    /// the number of states of the resulting DFA is not linear in the length of the `query`.
+    pub fn build_suffix_dfa(&self, query: &str) -> DFA {
+        self.parametric_dfa.build_suffix_dfa(query)
+    }
+
+    /// Returns the maximum distance considered by the automata built by this builder.
+    pub fn max_distance(&self) -> u8 {
+        self.parametric_dfa.max_distance()
+    }
+
+    /// Returns true iff transpositions are assigned a distance of 1.
+    pub fn transposition_cost_one(&self) -> bool {
+        self.transposition_cost_one
+    }
+
+    /// Returns the precomputed parametric DFA wrapped by this builder.
+    ///
+    /// See [ParametricDFA::build_dfa_from_state(...)](./struct.ParametricDFA.html#method.build_dfa_from_state).
+    pub fn parametric_dfa(&self) -> &ParametricDFA {
+        &self.parametric_dfa
+    }
 }
@@ -1,9 +1,20 @@
-use super::alphabet::Alphabet;
-use super::dfa::{Utf8DFABuilder, DFA};
+use super::alignment::AlignmentQuery;
+use super::alphabet::{Alphabet, FullCharacteristicVector};
+use super::dfa::{DFACompiler, Utf8DFABuilder, DFA};
+use super::error_model::{self, ErrorModel};
 use super::levenshtein_nfa::Distance;
 use super::levenshtein_nfa::{LevenshteinNFA, MultiState};
 use super::Index;
-
+use std::fmt;
+use std::mem;
+use std::sync::OnceLock;
+
+/// State of a [ParametricDFA](./struct.ParametricDFA.html) applied to a given query:
+/// a shape of the parametric DFA and the offset in the query at which it applies.
+///
+/// Equality and hashing are based on `(shape_id, offset)`. The dead end state
+/// always has an offset of `0`, so that two states are equal iff they compute
+/// the same distances.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct ParametricState {
     shape_id: u32,
@@ -22,13 +33,37 @@ impl ParametricState {
     }
 }
 
-#[derive(Clone, Copy)]
+impl fmt::Display for ParametricState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_dead_end() {
+            write!(f, "DEAD")
+        } else {
+            write!(f, "(shape={}, offset={})", self.shape_id, self.offset)
+        }
+    }
+}
+
+/// Transition of a [ParametricDFA](./struct.ParametricDFA.html).
+///
+/// It leads to the shape `dest_shape_id()`, and shifts the offset
+/// of the state by `delta_offset()`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Transition {
     dest_shape_id: u32,
     delta_offset: u32,
 }
 
 impl Transition {
+    /// Returns the shape id of the destination state.
+    pub fn dest_shape_id(&self) -> u32 {
+        self.dest_shape_id
+    }
+
+    /// Returns the increase of the offset of the state.
+    pub fn delta_offset(&self) -> u32 {
+        self.delta_offset
+    }
+
     fn apply(&self, state: ParametricState) -> ParametricState {
         ParametricState {
             shape_id: self.dest_shape_id,
@@ -42,6 +77,9 @@ impl Transition {
     }
 }
 
+// Number of states built between two calls to the progress callback.
+const PROGRESS_INTERVAL: u32 = 1024;
+
 struct ParametricStateIndex {
     state_index: Vec<Option<u32>>,
     state_queue: Vec<ParametricState>,
@@ -55,7 +93,7 @@ impl ParametricStateIndex {
         ParametricStateIndex {
             state_index: vec![None; max_num_states],
             state_queue: Vec::with_capacity(100),
-            num_offsets: num_offsets,
+            num_offsets,
         }
     }
 
@@ -82,6 +120,121 @@ impl ParametricStateIndex {
     fn get(&self, state_id: u32) -> ParametricState {
         self.state_queue[state_id as usize]
     }
+
+    fn into_states(self) -> Vec<ParametricState> {
+        self.state_queue
+    }
+}
+
+/// Compiles the [DFA] of the given query from a `ParametricDFA`.
+/// If `prefix` is set to `true`, the resulting DFA will match whenever
+/// the `query` is a prefix of the input being processed.
+///
+/// This is the function behind
+/// [ParametricDFA::build_dfa(...)](./struct.ParametricDFA.html#method.build_dfa).
+pub fn compile_dfa_from_parametric(pdfa: &ParametricDFA, query: &str, prefix: bool) -> DFA {
+    let query_len = query.chars().count();
+    let mut dfa_builder = Utf8DFABuilder::with_max_num_states(pdfa.num_states() * (query_len + 1));
+    compile_states(
+        pdfa,
+        query,
+        ParametricDFA::initial_state(),
+        prefix,
+        false,
+        &mut |_, _| {},
+        &mut dfa_builder,
+    );
+    dfa_builder.finish().with_alignment_query(AlignmentQuery {
+        chars: query.chars().collect(),
+        prefix,
+        transposition: pdfa.transposition,
+    })
+}
+
+// Same as `compile_dfa_from_parametric`, starting from the `initial` parametric state
+// and reporting the progress of the construction to `on_state`.
+fn compile_dfa(
+    pdfa: &ParametricDFA,
+    query: &str,
+    initial: ParametricState,
+    prefix: bool,
+    use_applied_distance: bool,
+    on_state: &mut dyn FnMut(usize, usize),
+) -> DFA {
+    let query_len = query.chars().count();
+    let mut dfa_builder = Utf8DFABuilder::with_max_num_states(pdfa.num_states() * (query_len + 1));
+    compile_states(
+        pdfa,
+        query,
+        initial,
+        prefix,
+        use_applied_distance,
+        on_state,
+        &mut dfa_builder,
+    );
+    dfa_builder.finish()
+}
+
+// Drives the `compiler` through the states of the DFA of the `query`, and hands it
+// the parametric state associated to each state id.
+fn compile_states<C: DFACompiler>(
+    pdfa: &ParametricDFA,
+    query: &str,
+    initial: ParametricState,
+    prefix: bool,
+    use_applied_distance: bool,
+    on_state: &mut dyn FnMut(usize, usize),
+    compiler: &mut C,
+) {
+    let query_chars: Vec<char> = query.chars().collect();
+    let query_len = query_chars.len();
+    let alphabet = Alphabet::for_query_chars(&query_chars);
+
+    let mut parametric_state_index = ParametricStateIndex::new(query_len, pdfa.num_states());
+    let max_num_states = parametric_state_index.max_num_states();
+
+    let dead_end_state_id = parametric_state_index.get_or_allocate(ParametricState::empty());
+    assert_eq!(dead_end_state_id, 0);
+    assert!(
+        initial.offset as usize <= query_len,
+        "Initial state offset is larger than the query length"
+    );
+    let initial_state_id = parametric_state_index.get_or_allocate(initial);
+
+    let mask = (1 << pdfa.diameter) - 1;
+
+    for state_id in 0u32.. {
+        if state_id == parametric_state_index.num_states() as u32 {
+            on_state(state_id as usize, max_num_states);
+            break;
+        }
+        if state_id > 0 && state_id % PROGRESS_INTERVAL == 0 {
+            on_state(state_id as usize, max_num_states);
+        }
+        let state = parametric_state_index.get(state_id);
+        let distance = if use_applied_distance {
+            pdfa.applied_distance(state)
+        } else {
+            pdfa.distance(state, query_len)
+        };
+
+        if prefix && pdfa.is_prefix_sink(state, query_len) {
+            compiler.begin_state(state_id, distance, state_id);
+        } else {
+            let default_successor = pdfa.transition(state, 0u32).apply(state);
+            let default_successor_id = parametric_state_index.get_or_allocate(default_successor);
+            compiler.begin_state(state_id, distance, default_successor_id);
+            for (chr, characteristic_vec) in alphabet.iter() {
+                let chi = characteristic_vec.shift_and_mask(state.offset as usize, mask);
+                let dest_state: ParametricState = pdfa.transition(state, chi).apply(state);
+                let dest_state_id = parametric_state_index.get_or_allocate(dest_state);
+                compiler.add_char_transition(state_id, *chr, dest_state_id);
+            }
+        }
+    }
+
+    compiler.set_initial(initial_state_id);
+    compiler.set_parametric_states(parametric_state_index.into_states());
 }
 
 pub struct ParametricDFA {
@@ -90,6 +243,7 @@ pub struct ParametricDFA {
     max_distance: u8,
     transition_stride: usize,
     diameter: usize,
+    transposition: bool,
 }
 
 impl ParametricDFA {
@@ -100,6 +254,31 @@ impl ParametricDFA {
         }
     }
 
+    /// Returns the parametric DFA for one of the most common configurations:
+    /// a maximum distance `d` in `1..=4`, with or without transposition.
+    ///
+    /// Each parametric DFA is built once, on the first call with its configuration.
+    /// Returns `None` if `max_distance` is not in `1..=4`.
+    pub fn standard(max_distance: u8, transposition: bool) -> Option<&'static ParametricDFA> {
+        static STANDARD_PARAMETRIC_DFAS: [OnceLock<ParametricDFA>; 8] = [
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+        ];
+        if !(1..=4).contains(&max_distance) {
+            return None;
+        }
+        let config_id = 2 * (max_distance as usize - 1) + transposition as usize;
+        Some(STANDARD_PARAMETRIC_DFAS[config_id].get_or_init(|| {
+            ParametricDFA::from_nfa(&LevenshteinNFA::levenshtein(max_distance, transposition))
+        }))
+    }
+
     // Returns true iff whatever characters come afterward, we will never reach
     // a shorter distance
     fn is_prefix_sink(&self, state: ParametricState, query_len: usize) -> bool {
@@ -126,8 +305,10 @@ impl ParametricDFA {
 
     /// Builds a [DFA] for the given query. If `prefix` is set to `true`, the resulting
     /// DFA will match whenever the `query` is a prefix of the input being processed.
+    ///
+    /// See [compile_dfa_from_parametric(...)](./fn.compile_dfa_from_parametric.html).
     pub fn build_dfa(&self, query: &str, prefix: bool) -> DFA {
-        self.build_custom_dfa(query, prefix, false)
+        compile_dfa_from_parametric(self, query, prefix)
     }
 
     /// Builds a [DFA] for the given query. If `prefix` is set to `true`, the resulting
@@ -136,49 +317,285 @@ impl ParametricDFA {
     /// "absolute distance" for the query but rather the distance (number of edits) which
     /// have been applied so far.
     pub fn build_custom_dfa(&self, query: &str, prefix: bool, use_applied_distance: bool) -> DFA {
+        compile_dfa(
+            self,
+            query,
+            ParametricDFA::initial_state(),
+            prefix,
+            use_applied_distance,
+            &mut |_, _| {},
+        )
+    }
+
+    /// Same as [build_dfa(...)](#method.build_dfa), but calls
+    /// `on_state(states_built, max_states)` every `1024` states, and once
+    /// all of the states have been built.
+    ///
+    /// States are counted before they are encoded as UTF-8, which adds
+    /// intermediate states to the final `DFA`. `max_states` is an upper bound of
+    /// `states_built`. This makes it possible to report the progress of the
+    /// construction of the `DFA`s of very long queries.
+    pub fn build_dfa_with_progress<F: FnMut(usize, usize)>(
+        &self,
+        query: &str,
+        prefix: bool,
+        mut on_state: F,
+    ) -> DFA {
+        compile_dfa(
+            self,
+            query,
+            ParametricDFA::initial_state(),
+            prefix,
+            false,
+            &mut on_state,
+        )
+    }
+
+    /// Builds a [DFA] for the given query, starting from an arbitrary `initial` parametric
+    /// state rather than `ParametricDFA::initial_state()`.
+    ///
+    /// This makes it possible to resume the evaluation of a text that has been partially
+    /// consumed, for instance when the text is received as a stream of segments.
+    /// The state reached after the consumed segments is given by
+    /// [compute_state(...)](#method.compute_state).
+    pub fn build_dfa_from_state(&self, query: &str, initial: ParametricState, prefix: bool) -> DFA {
+        compile_dfa(self, query, initial, prefix, false, &mut |_, _| {})
+    }
+
+    /// Same as [build_dfa(...)](#method.build_dfa), but the states and transitions
+    /// of the `DFA` are handed to a custom [DFACompiler](./trait.DFACompiler.html),
+    /// which is in charge of building the `DFA`, or any other representation of it.
+    pub fn build_dfa_with_compiler<C: DFACompiler>(
+        &self,
+        query: &str,
+        prefix: bool,
+        mut compiler: C,
+    ) -> C::Output {
+        compile_states(
+            self,
+            query,
+            ParametricDFA::initial_state(),
+            prefix,
+            false,
+            &mut |_, _| {},
+            &mut compiler,
+        );
+        compiler.finish()
+    }
+
+    /// Builds a [DFA] computing the distance to the given query, with the
+    /// edit costs given by an [ErrorModel](./trait.ErrorModel.html).
+    ///
+    /// Distances above the `max_distance` of the `ParametricDFA` are reported
+    /// as `Distance::AtLeast(max_distance + 1)`. Transpositions are not supported.
+    ///
+    /// With a [UnitErrorModel](./struct.UnitErrorModel.html), the resulting `DFA`
+    /// computes the same distances as [build_dfa(...)](#method.build_dfa)
+    /// without transpositions.
+    pub fn build_dfa_with_error_model(&self, query: &str, model: &dyn ErrorModel) -> DFA {
+        error_model::build_dfa(query, self.max_distance, model)
+    }
+
+    /// Builds a [DFA] for the given query, that only accepts the strings
+    /// whose distance to the `query` belongs to `[lo, hi]`.
+    ///
+    /// The strings closer than `lo` to the `query` are rejected with
+    /// `Distance::AtLeast(0)`. The strings farther than `hi` are reported
+    /// as `Distance::AtLeast(hi + 1)`, or `Distance::AtLeast(max_distance + 1)`
+    /// if they are farther than the `max_distance`.
+    pub fn build_range_dfa(&self, query: &str, lo: u8, hi: u8) -> DFA {
+        assert!(lo <= hi, "Lower bound is larger than the upper bound");
+        assert!(
+            hi <= self.max_distance,
+            "Upper bound is larger than max_distance"
+        );
+        self.build_dfa(query, false).restrict_distances(lo..=hi)
+    }
+
+    /// Builds a [DFA] that computes the minimum distance between the `query` and
+    /// the suffixes of the input being processed.
+    ///
+    /// Each state of the resulting DFA is a set of parametric states: one for each
+    /// position of the input at which a suffix could start and that has not yet
+    /// reached the dead end state.
+    pub fn build_suffix_dfa(&self, query: &str) -> DFA {
         let query_chars: Vec<char> = query.chars().collect();
         let query_len = query_chars.len();
         let alphabet = Alphabet::for_query_chars(&query_chars);
+        let mask = (1 << self.diameter) - 1;
 
-        let mut parametric_state_index = ParametricStateIndex::new(query_len, self.num_states());
-        let max_num_states = parametric_state_index.max_num_states();
+        let mut index: Index<Vec<ParametricState>> = Index::new();
+        // The sink state is not reachable, as a new suffix can start at any character,
+        // but the DFA still requires it.
+        let sink_state_id = index.get_or_allocate(&Vec::new());
+        assert_eq!(sink_state_id, 0);
+        let initial_state_id = index.get_or_allocate(&vec![ParametricDFA::initial_state()]);
+
+        let successor =
+            |states: &[ParametricState],
+             chr_characteristic_vec: Option<&FullCharacteristicVector>| {
+                let mut dest_states: Vec<ParametricState> = states
+                    .iter()
+                    .map(|&state| {
+                        let chi = chr_characteristic_vec
+                            .map(|characteristic_vec| {
+                                characteristic_vec.shift_and_mask(state.offset as usize, mask)
+                            })
+                            .unwrap_or(0u32);
+                        self.transition(state, chi).apply(state)
+                    })
+                    .filter(|state| !state.is_dead_end())
+                    .collect();
+                dest_states.push(ParametricDFA::initial_state());
+                dest_states.sort_by_key(|state| (state.shape_id, state.offset));
+                dest_states.dedup();
+                dest_states
+            };
 
-        let dead_end_state_id = parametric_state_index.get_or_allocate(ParametricState::empty());
-        assert_eq!(dead_end_state_id, 0);
-        let initial_state_id =
-            parametric_state_index.get_or_allocate(ParametricDFA::initial_state());
+        // (distance, default successor, successor for each character of the alphabet)
+        let mut suffix_states: Vec<(Distance, u32, Vec<u32>)> = Vec::new();
+        for state_id in 0u32.. {
+            if state_id == index.len() {
+                break;
+            }
+            let states = index.get_from_id(state_id).clone();
+            if state_id == sink_state_id {
+                suffix_states.push((
+                    Distance::AtLeast(self.max_distance + 1u8),
+                    sink_state_id,
+                    Vec::new(),
+                ));
+                continue;
+            }
+            let distance = states
+                .iter()
+                .filter_map(|&state| match self.distance(state, query_len) {
+                    Distance::Exact(d) => Some(d),
+                    Distance::AtLeast(_) => None,
+                })
+                .min()
+                .map(Distance::Exact)
+                .unwrap_or_else(|| Distance::AtLeast(self.max_distance + 1u8));
+            let default_successor_id = index.get_or_allocate(&successor(&states, None));
+            let successor_ids: Vec<u32> = alphabet
+                .iter()
+                .map(|(_, characteristic_vec)| {
+                    index.get_or_allocate(&successor(&states, Some(characteristic_vec)))
+                })
+                .collect();
+            suffix_states.push((distance, default_successor_id, successor_ids));
+        }
+
+        let mut dfa_builder = Utf8DFABuilder::with_max_num_states(suffix_states.len());
+        for (state_id, (distance, default_successor_id, successor_ids)) in
+            suffix_states.into_iter().enumerate()
+        {
+            let mut state_builder =
+                dfa_builder.add_state(state_id as u32, distance, default_successor_id);
+            for ((chr, _), dest_state_id) in alphabet.iter().zip(successor_ids) {
+                state_builder.add_transition(*chr, dest_state_id);
+            }
+        }
+        dfa_builder.set_initial_state(initial_state_id);
+        dfa_builder.build()
+    }
 
-        let mut dfa_builder = Utf8DFABuilder::with_max_num_states(max_num_states);
+    /// Builds an approximate [DFA] for long queries, by splitting the `query` into chunks
+    /// of `chunk_size` characters, matched one after the other.
+    ///
+    /// The text is matched against the first chunk until the distance to that chunk
+    /// cannot decrease anymore. This distance is then accumulated, and the rest of the
+    /// text is matched against the following chunk, and so forth.
+    /// The distance reported is the sum of the distances to each chunk.
+    ///
+    /// Because moving from a chunk to the next is decided greedily, the reported
+    /// distance may overestimate the actual distance when an edit happens at
+    /// the boundary between two chunks.
+    pub fn build_dfa_chunked(&self, query: &str, chunk_size: usize) -> DFA {
+        assert!(chunk_size > 0, "Chunk size must be strictly positive");
+        let query_chars: Vec<char> = query.chars().collect();
+        let mut chunks: Vec<&[char]> = query_chars.chunks(chunk_size).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        let last_chunk = chunks.len() - 1;
+        // Number of characters in the chunks following each chunk.
+        let mut remaining_lens = vec![0usize; chunks.len()];
+        for chunk_id in (0..last_chunk).rev() {
+            remaining_lens[chunk_id] = remaining_lens[chunk_id + 1] + chunks[chunk_id + 1].len();
+        }
+        let alphabets: Vec<Alphabet> = chunks
+            .iter()
+            .map(|chunk| Alphabet::for_query_chars(chunk))
+            .collect();
         let mask = (1 << self.diameter) - 1;
+        let max_distance = u32::from(self.max_distance);
+        let dead_state = (0usize, ParametricState::empty(), 0u32);
+
+        // Moves to the following chunks as long as the distance to the current
+        // chunk cannot decrease anymore.
+        let normalize = |mut chunk_id: usize, mut state: ParametricState, mut acc_distance: u32| loop {
+            if state.is_dead_end() || acc_distance > max_distance {
+                return dead_state;
+            }
+            let chunk_len = chunks[chunk_id].len();
+            if chunk_id == last_chunk || !self.is_prefix_sink(state, chunk_len) {
+                return (chunk_id, state, acc_distance);
+            }
+            acc_distance += u32::from(self.distance(state, chunk_len).distance_value());
+            chunk_id += 1;
+            state = ParametricDFA::initial_state();
+        };
+
+        let mut index: Index<(usize, ParametricState, u32)> = Index::new();
+        let dead_state_id = index.get_or_allocate(&dead_state);
+        assert_eq!(dead_state_id, 0);
+        let initial_state_id =
+            index.get_or_allocate(&normalize(0, ParametricDFA::initial_state(), 0));
 
+        // (distance, default successor, successor for each character of the chunk alphabet)
+        let mut chunked_states: Vec<(Distance, u32, Vec<u32>)> = Vec::new();
         for state_id in 0u32.. {
-            if state_id == parametric_state_index.num_states() as u32 {
+            if state_id == index.len() {
                 break;
             }
-            let state = parametric_state_index.get(state_id);
-            let distance = if use_applied_distance {
-                self.applied_distance(state)
+            let (chunk_id, state, acc_distance) = *index.get_from_id(state_id);
+            let chunk_len = chunks[chunk_id].len();
+            let distance = if state.is_dead_end() {
+                Distance::AtLeast(self.max_distance + 1u8)
             } else {
-                self.distance(state, query_len)
+                let chunk_distance = u32::from(self.distance(state, chunk_len).distance_value());
+                let d = acc_distance + chunk_distance + remaining_lens[chunk_id] as u32;
+                if d <= max_distance {
+                    Distance::Exact(d as u8)
+                } else {
+                    Distance::AtLeast(self.max_distance + 1u8)
+                }
             };
-
-            if prefix && self.is_prefix_sink(state, query_len) {
-                dfa_builder.add_state(state_id, distance, state_id);
-            } else {
-                let default_successor = self.transition(state, 0u32).apply(state);
-                let default_successor_id =
-                    parametric_state_index.get_or_allocate(default_successor);
-                let mut state_builder =
-                    dfa_builder.add_state(state_id, distance, default_successor_id);
-                for &(ref chr, ref characteristic_vec) in alphabet.iter() {
+            let default_successor = self.transition(state, 0u32).apply(state);
+            let default_successor_id =
+                index.get_or_allocate(&normalize(chunk_id, default_successor, acc_distance));
+            let successor_ids: Vec<u32> = alphabets[chunk_id]
+                .iter()
+                .map(|(_, characteristic_vec)| {
                     let chi = characteristic_vec.shift_and_mask(state.offset as usize, mask);
-                    let dest_state: ParametricState = self.transition(state, chi).apply(state);
-                    let dest_state_id = parametric_state_index.get_or_allocate(dest_state);
-                    state_builder.add_transition(*chr, dest_state_id);
-                }
-            }
+                    let dest_state = self.transition(state, chi).apply(state);
+                    index.get_or_allocate(&normalize(chunk_id, dest_state, acc_distance))
+                })
+                .collect();
+            chunked_states.push((distance, default_successor_id, successor_ids));
         }
 
+        let mut dfa_builder = Utf8DFABuilder::with_max_num_states(chunked_states.len());
+        for (state_id, (distance, default_successor_id, successor_ids)) in
+            index.ids().zip(chunked_states)
+        {
+            let (chunk_id, _, _) = *index.get_from_id(state_id);
+            let mut state_builder = dfa_builder.add_state(state_id, distance, default_successor_id);
+            for ((chr, _), dest_state_id) in alphabets[chunk_id].iter().zip(successor_ids) {
+                state_builder.add_transition(*chr, dest_state_id);
+            }
+        }
         dfa_builder.set_initial_state(initial_state_id);
         dfa_builder.build()
     }
@@ -187,36 +604,101 @@ impl ParametricDFA {
         self.transitions.len() / self.transition_stride
     }
 
+    /// Returns an estimate of the number of bytes used by the transition table
+    /// and the distances of a `DFA` built for a query of `query_len` chars.
+    ///
+    /// The number of states is estimated as `num_states() * (query_len + 1)`,
+    /// which bounds the number of states before the chars are encoded as UTF-8.
+    /// The states added by the UTF-8 encoding are not taken into account, so that
+    /// for small distances, the `DFA` may be slightly larger than the estimate.
+    pub fn estimate_concrete_dfa_size(&self, query_len: usize) -> usize {
+        let max_num_states = self.num_states().saturating_mul(query_len + 1);
+        let state_size = mem::size_of::<[u32; 256]>() + mem::size_of::<Distance>();
+        max_num_states.saturating_mul(state_size)
+    }
+
+    /// Returns true iff `self` and `other` have the same transitions and
+    /// distances, i.e. they build the same `DFA`s.
+    pub fn is_equivalent_to(&self, other: &ParametricDFA) -> bool {
+        self.max_distance == other.max_distance
+            && self.diameter == other.diameter
+            && self.transition_stride == other.transition_stride
+            && self.transitions == other.transitions
+            && self.distance == other.distance
+    }
+
+    /// Returns the maximum distance computed by the `DFA`s built from this `ParametricDFA`.
+    pub fn max_distance(&self) -> u8 {
+        self.max_distance
+    }
+
+    /// Shrinks the capacity of the distance and transition tables as much as possible.
+    ///
+    /// This is worth calling on `ParametricDFA`s that are kept alive
+    /// for the lifetime of the process.
+    pub fn shrink_to_fit(&mut self) {
+        self.distance.shrink_to_fit();
+        self.transitions.shrink_to_fit();
+    }
+
     // only for debug
     #[cfg(test)]
     pub fn compute_distance(&self, left: &str, right: &str) -> Distance {
+        let state = self.compute_state(Self::initial_state(), left, right);
+        if state.is_dead_end() {
+            return Distance::AtLeast(self.max_distance + 1u8);
+        }
+        self.distance(state, left.len())
+    }
+
+    /// Returns the parametric state reached from `initial` after consuming
+    /// the chars of `text`, when computing the distance to `query`.
+    ///
+    /// The resulting state can be given to
+    /// [build_dfa_from_state(...)](#method.build_dfa_from_state) to build a `DFA`
+    /// that resumes the evaluation after `text`.
+    pub fn compute_state(
+        &self,
+        initial: ParametricState,
+        query: &str,
+        text: &str,
+    ) -> ParametricState {
         use super::levenshtein_nfa::compute_characteristic_vector;
         use std::cmp;
-        let mut state = Self::initial_state();
-        let left_chars: Vec<char> = left.chars().collect();
-        for chr in right.chars() {
+        let mut state = initial;
+        let query_chars: Vec<char> = query.chars().collect();
+        for chr in text.chars() {
             let start = state.offset as usize;
-            let stop = cmp::min(start + self.diameter, left_chars.len());
-            let chi = compute_characteristic_vector(&left_chars[start..stop], chr) as u32;
+            let stop = cmp::min(start + self.diameter, query_chars.len());
+            let chi = compute_characteristic_vector(&query_chars[start..stop], chr) as u32;
             state = self.transition(state, chi).apply(state);
             if state.is_dead_end() {
-                return Distance::AtLeast(self.max_distance + 1u8);
+                break;
             }
         }
-        self.distance(state, left.len())
+        state
     }
 
     pub fn distance(&self, state: ParametricState, query_len: usize) -> Distance {
         let remaining_offset: usize = query_len - state.offset as usize;
-        if state.is_dead_end() || remaining_offset >= self.diameter {
+        if state.is_dead_end() {
             Distance::AtLeast(self.max_distance + 1u8)
         } else {
-            let d = self.distance[(self.diameter * state.shape_id as usize) + remaining_offset];
-            if d > self.max_distance {
-                Distance::AtLeast(d)
-            } else {
-                Distance::Exact(d)
-            }
+            self.distance_for_shape(state.shape_id, remaining_offset)
+        }
+    }
+
+    /// Same as [distance(...)](#method.distance), for a raw shape id and the number
+    /// of chars of the query that remain after the offset of the state.
+    pub fn distance_for_shape(&self, shape_id: u32, remaining_offset: usize) -> Distance {
+        if remaining_offset >= self.diameter {
+            return Distance::AtLeast(self.max_distance + 1u8);
+        }
+        let d = self.distance[(self.diameter * shape_id as usize) + remaining_offset];
+        if d > self.max_distance {
+            Distance::AtLeast(d)
+        } else {
+            Distance::Exact(d)
         }
     }
 
@@ -230,8 +712,24 @@ impl ParametricDFA {
     }
 
     pub fn transition(&self, state: ParametricState, chi: u32) -> Transition {
+        self.transition_for_id(state.shape_id, chi)
+    }
+
+    /// Same as [transition(...)](#method.transition), for a raw shape id
+    /// rather than a `ParametricState`.
+    pub fn transition_for_id(&self, shape_id: u32, chi: u32) -> Transition {
         assert!((chi as usize) < self.transition_stride);
-        self.transitions[self.transition_stride * state.shape_id as usize + chi as usize]
+        self.transitions[self.transition_stride * shape_id as usize + chi as usize]
+    }
+
+    /// Returns the number of transitions going out of the shape `shape_id`,
+    /// i.e. the number of distinct characteristic vectors.
+    ///
+    /// All of the shapes have `2 ^ diameter` outgoing transitions, one for each `chi`
+    /// accepted by [transition_for_id(...)](#method.transition_for_id).
+    pub fn transition_count_for_shape(&self, shape_id: u32) -> usize {
+        assert!((shape_id as usize) < self.num_states());
+        self.transition_stride
     }
 
     pub fn from_nfa(nfa: &LevenshteinNFA) -> ParametricDFA {
@@ -240,7 +738,6 @@ impl ParametricDFA {
         let initial_state = nfa.initial_states();
         index.get_or_allocate(&initial_state);
 
-        let max_distance = nfa.max_distance();
         let multistate_diameter = nfa.multistate_diameter();
         let mut transitions: Vec<Transition> = vec![];
 
@@ -267,24 +764,82 @@ impl ParametricDFA {
             }
         }
 
+        ParametricDFA::from_multistates(nfa, &index, transitions)
+    }
+
+    /// Same as [from_nfa(...)](#method.from_nfa), but the transitions of the
+    /// states discovered at each step of the exploration are computed in parallel.
+    ///
+    /// The resulting parametric DFA is identical to the one returned by `from_nfa`.
+    #[cfg(feature = "rayon")]
+    pub fn from_nfa_parallel(nfa: &LevenshteinNFA) -> ParametricDFA {
+        use rayon::prelude::*;
+
+        let mut index: Index<MultiState> = Index::new();
+        index.get_or_allocate(&MultiState::empty());
+        let initial_state = nfa.initial_states();
+        index.get_or_allocate(&initial_state);
+
+        let multistate_diameter = nfa.multistate_diameter();
+        let mut transitions: Vec<Transition> = vec![];
+
+        let num_chi = 1u64 << multistate_diameter;
+
+        let mut num_explored_states = 0;
+        while num_explored_states < index.len() {
+            let dest_multistates: Vec<Vec<(MultiState, u32)>> = (num_explored_states..index.len())
+                .into_par_iter()
+                .map(|state_id| {
+                    let multistate: &MultiState = index.get_from_id(state_id);
+                    (0..num_chi)
+                        .map(|chi| {
+                            let mut dest_multistate = MultiState::empty();
+                            nfa.transition(multistate, &mut dest_multistate, chi);
+                            let translation = dest_multistate.normalize();
+                            (dest_multistate, translation)
+                        })
+                        .collect()
+                })
+                .collect();
+            num_explored_states = index.len();
+            for (dest_multistate, translation) in dest_multistates.into_iter().flatten() {
+                let dest_id = index.get_or_allocate(&dest_multistate);
+                transitions.push(Transition {
+                    dest_shape_id: dest_id,
+                    delta_offset: translation,
+                });
+            }
+        }
+
+        ParametricDFA::from_multistates(nfa, &index, transitions)
+    }
+
+    fn from_multistates(
+        nfa: &LevenshteinNFA,
+        index: &Index<MultiState>,
+        transitions: Vec<Transition>,
+    ) -> ParametricDFA {
         let num_states = index.len();
-        let multistate_diameter = multistate_diameter as usize;
+        let multistate_diameter = nfa.multistate_diameter() as usize;
+        let num_chi = 1 << multistate_diameter;
         let mut distance: Vec<u8> = Vec::with_capacity(multistate_diameter * num_states as usize);
 
-        for state_id in 0..num_states {
-            let multistate = index.get_from_id(state_id);
+        for multistate in index.values() {
             for offset in 0..multistate_diameter {
-                let dist = nfa.multistate_distance(multistate, offset as u32).to_u8();
+                let dist = nfa
+                    .multistate_distance(multistate, offset as u32)
+                    .distance_value();
                 distance.push(dist);
             }
         }
 
         ParametricDFA {
-            transition_stride: num_chi as usize,
+            transition_stride: num_chi,
             distance,
-            max_distance,
+            max_distance: nfa.max_distance(),
             transitions,
-            diameter: multistate_diameter as usize,
+            diameter: multistate_diameter,
+            transposition: nfa.is_damerau(),
         }
     }
 }
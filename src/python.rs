@@ -0,0 +1,60 @@
+//! Python bindings, built with [maturin](https://github.com/PyO3/maturin).
+//!
+//! ```python
+//! from levenshtein_automata import LevenshteinAutomatonBuilder
+//!
+//! builder = LevenshteinAutomatonBuilder(2, True)
+//! dfa = builder.build_dfa("Levenshtein")
+//! assert dfa.eval("Levenshtain") == 1
+//! ```
+
+use super::{LevenshteinAutomatonBuilder, DFA};
+use pyo3::prelude::*;
+
+#[pyclass(name = "LevenshteinAutomatonBuilder", frozen)]
+struct PyLevenshteinAutomatonBuilder {
+    builder: LevenshteinAutomatonBuilder,
+}
+
+#[pymethods]
+impl PyLevenshteinAutomatonBuilder {
+    #[new]
+    fn new(max_distance: u8, transposition_cost_one: bool) -> PyLevenshteinAutomatonBuilder {
+        PyLevenshteinAutomatonBuilder {
+            builder: LevenshteinAutomatonBuilder::new(max_distance, transposition_cost_one),
+        }
+    }
+
+    fn build_dfa(&self, query: &str) -> PyDFA {
+        PyDFA {
+            dfa: self.builder.build_dfa(query),
+        }
+    }
+
+    fn build_prefix_dfa(&self, query: &str) -> PyDFA {
+        PyDFA {
+            dfa: self.builder.build_prefix_dfa(query),
+        }
+    }
+}
+
+#[pyclass(name = "DFA", frozen)]
+struct PyDFA {
+    dfa: DFA,
+}
+
+#[pymethods]
+impl PyDFA {
+    /// Returns the distance of `text` to the query, or `max_distance + 1`
+    /// if it is larger than the maximum distance of the builder.
+    fn eval(&self, text: &str) -> u8 {
+        self.dfa.eval(text).distance_value()
+    }
+}
+
+#[pymodule]
+fn levenshtein_automata(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyLevenshteinAutomatonBuilder>()?;
+    module.add_class::<PyDFA>()?;
+    Ok(())
+}
@@ -1,4 +1,11 @@
-use crate::{Distance, LevenshteinNFA, ParametricDFA};
+use crate::index::Index;
+use crate::parametric_dfa::ParametricState;
+use crate::{
+    compile_dfa_from_parametric, AlignKind, AlignOp, ByteClassError, CompactDFA, DFAParts,
+    Distance, DynamicLevenshteinDistance, ErrorModel, InvalidDFA, LevenshteinAutomatonBuilder,
+    LevenshteinNFA, MultiState, NFAState, OverflowError, ParametricDFA, StateIdType, Trie,
+    UnitErrorModel, DFA, SINK_STATE,
+};
 use std::collections::HashSet;
 
 fn make_distance(n: u8, max_distance: u8) -> Distance {
@@ -64,11 +71,11 @@ fn test_levenshtein_dfa_slow() {
 
     for left in test_sample.lefts() {
         for m in 0..4u8 {
-            let dfa = parametric_dfas[m as usize].build_dfa(&left, false);
+            let dfa = parametric_dfas[m as usize].build_dfa(left, false);
             for right in test_sample.rights() {
-                let expected = levenshtein::levenshtein(&left, &right) as u8;
+                let expected = levenshtein::levenshtein(left, right) as u8;
                 let expected_distance = make_distance(expected, m);
-                let result_distance = dfa.eval(&right);
+                let result_distance = dfa.eval(right);
                 assert_eq!(expected_distance, result_distance);
             }
         }
@@ -276,8 +283,8 @@ fn test_prefix() {
     let parametric_dfa = ParametricDFA::from_nfa(&nfa);
     let dfa = parametric_dfa.build_dfa(q, true);
     assert_eq!(dfa.eval(q), Distance::Exact(0u8));
-    assert_eq!(dfa.eval(&"a"), Distance::AtLeast(1u8));
-    assert_eq!(dfa.eval(&"ab"), Distance::AtLeast(1u8));
+    assert_eq!(dfa.eval("a"), Distance::AtLeast(1u8));
+    assert_eq!(dfa.eval("ab"), Distance::AtLeast(1u8));
     for d in 3..10 {
         assert_eq!(dfa.eval(&"abcdefghij"[..d]), Distance::Exact(0u8));
     }
@@ -289,11 +296,11 @@ fn test_applied_distance() {
     let nfa = LevenshteinNFA::levenshtein(1, true);
     let parametric_dfa = ParametricDFA::from_nfa(&nfa);
     let dfa = parametric_dfa.build_custom_dfa(q, true, true);
-    assert_eq!(dfa.eval(&"abcde"), Distance::Exact(0u8));
-    assert_eq!(dfa.eval(&"abcd"), Distance::Exact(0u8));
-    assert_eq!(dfa.eval(&"abde"), Distance::Exact(1u8));
-    assert_eq!(dfa.eval(&"abdce"), Distance::Exact(1u8));
-    assert_eq!(dfa.eval(&"abbbb"), Distance::AtLeast(2u8));
+    assert_eq!(dfa.eval("abcde"), Distance::Exact(0u8));
+    assert_eq!(dfa.eval("abcd"), Distance::Exact(0u8));
+    assert_eq!(dfa.eval("abde"), Distance::Exact(1u8));
+    assert_eq!(dfa.eval("abdce"), Distance::Exact(1u8));
+    assert_eq!(dfa.eval("abbbb"), Distance::AtLeast(2u8));
 }
 
 fn test_prefix_aux(
@@ -428,3 +435,1769 @@ fn test_prefix_dfa_1_damerau() {
         Distance::Exact(1),
     );
 }
+
+#[test]
+fn test_build_dfa_from_state() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "levenshtein";
+    for &(head, tail) in [
+        ("lev", "enshtein"),
+        ("lvens", "htein"),
+        ("levenXh", "teiin"),
+    ]
+    .iter()
+    {
+        let state: ParametricState =
+            parametric_dfa.compute_state(ParametricDFA::initial_state(), query, head);
+        let dfa = parametric_dfa.build_dfa_from_state(query, state, false);
+        let full_text = format!("{}{}", head, tail);
+        assert_eq!(
+            dfa.eval(tail),
+            parametric_dfa.build_dfa(query, false).eval(&full_text)
+        );
+    }
+    let state = parametric_dfa.compute_state(ParametricDFA::initial_state(), query, "XXX");
+    let dfa = parametric_dfa.build_dfa_from_state(query, state, false);
+    assert_eq!(dfa.eval("enshtein"), Distance::AtLeast(3));
+}
+
+#[test]
+fn test_transition_for_id() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let initial_state = ParametricDFA::initial_state();
+    let state = parametric_dfa.compute_state(initial_state, "abc", "a");
+    for chi in 0u32..8u32 {
+        assert_eq!(
+            parametric_dfa.transition_for_id(1, chi),
+            parametric_dfa.transition(initial_state, chi)
+        );
+    }
+    // Matching the first char of the query consumes it.
+    let transition = parametric_dfa.transition_for_id(1, 0b111);
+    assert_eq!(transition.delta_offset(), 1);
+    assert_eq!(
+        state.to_string(),
+        format!("(shape={}, offset=1)", transition.dest_shape_id())
+    );
+}
+
+#[test]
+fn test_transition_count_for_shape() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    for shape_id in 0..parametric_dfa.num_states() as u32 {
+        assert_eq!(parametric_dfa.transition_count_for_shape(shape_id), 8);
+    }
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    assert_eq!(parametric_dfa.transition_count_for_shape(1), 32);
+}
+
+#[test]
+fn test_estimate_concrete_dfa_size() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "abcdefghij";
+    let estimate = parametric_dfa.estimate_concrete_dfa_size(query.len());
+    assert_eq!(estimate, parametric_dfa.num_states() * 11 * (1024 + 2));
+    let dfa = parametric_dfa.build_dfa(query, false);
+    assert!(dfa.num_states() * (1024 + 2) <= 2 * estimate);
+    assert_eq!(
+        parametric_dfa.estimate_concrete_dfa_size(usize::MAX - 1),
+        usize::MAX
+    );
+}
+
+#[test]
+fn test_parametric_dfa_is_equivalent_to() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    assert!(parametric_dfa.is_equivalent_to(&ParametricDFA::from_nfa(&nfa)));
+    let other_nfa = LevenshteinNFA::levenshtein(2, false);
+    assert!(!parametric_dfa.is_equivalent_to(&ParametricDFA::from_nfa(&other_nfa)));
+    let other_nfa = LevenshteinNFA::levenshtein(1, true);
+    assert!(!parametric_dfa.is_equivalent_to(&ParametricDFA::from_nfa(&other_nfa)));
+}
+
+#[test]
+fn test_parametric_state_hash() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let initial_state = ParametricDFA::initial_state();
+    let states: HashSet<ParametricState> = ["x", "xy", "xyz", "a", "ab", "b"]
+        .iter()
+        .map(|text| parametric_dfa.compute_state(initial_state, "abc", text))
+        .collect();
+    assert_eq!(states.len(), 5);
+    let dead_state = parametric_dfa.compute_state(initial_state, "abc", "xyz");
+    assert!(states.contains(&dead_state));
+    assert_eq!(
+        dead_state,
+        parametric_dfa.compute_state(initial_state, "abcdef", "xyz")
+    );
+}
+
+#[test]
+fn test_distance_for_shape() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let initial_state = ParametricDFA::initial_state();
+    for query_len in 0..4 {
+        assert_eq!(
+            parametric_dfa.distance_for_shape(1, query_len),
+            parametric_dfa.distance(initial_state, query_len)
+        );
+    }
+    assert_eq!(parametric_dfa.distance_for_shape(1, 0), Distance::Exact(0));
+    assert_eq!(parametric_dfa.distance_for_shape(1, 1), Distance::Exact(1));
+    assert_eq!(
+        parametric_dfa.distance_for_shape(1, 5),
+        Distance::AtLeast(2)
+    );
+}
+
+#[test]
+fn test_parametric_state_display() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let initial_state = ParametricDFA::initial_state();
+    assert_eq!(initial_state.to_string(), "(shape=1, offset=0)");
+    let state = parametric_dfa.compute_state(initial_state, "abc", "a");
+    assert!(state.to_string().ends_with("offset=1)"));
+    let dead_state = parametric_dfa.compute_state(initial_state, "abc", "XX");
+    assert_eq!(dead_state.to_string(), "DEAD");
+}
+
+#[test]
+fn test_is_cyclic() {
+    for &damerau in [false, true].iter() {
+        for d in 0..3 {
+            let nfa = LevenshteinNFA::levenshtein(d, damerau);
+            let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+            for query in ["", "a", "abc", "levenshtein", "寿司は焦げられない"].iter() {
+                assert!(!parametric_dfa.build_dfa(query, false).is_cyclic());
+                assert!(parametric_dfa.build_dfa(query, true).is_cyclic());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reverse() {
+    let nfa = LevenshteinNFA::levenshtein(1, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abあc", false);
+    let reversed_dfa = dfa.reverse();
+    assert_eq!(reversed_dfa.eval(b"X"), Distance::AtLeast(2));
+    for text in [
+        "abあc", "abc", "aあc", "abあcd", "bあc", "baあc", "abいc", "ab", "", "xyz",
+    ]
+    .iter()
+    {
+        let reversed_text: Vec<u8> = text.bytes().rev().collect();
+        let expected = match dfa.eval(text) {
+            Distance::Exact(d) => Distance::Exact(d),
+            Distance::AtLeast(_) => Distance::AtLeast(2),
+        };
+        assert_eq!(reversed_dfa.eval(&reversed_text), expected, "{}", text);
+    }
+    assert_eq!(reversed_dfa.transition(SINK_STATE, b'a'), SINK_STATE);
+}
+
+#[test]
+fn test_suffix_dfa() {
+    let test_sample = TestSample::with_num_chars(4, "abあ", false);
+    for &damerau in [false, true].iter() {
+        for m in 0u8..3u8 {
+            let lev = LevenshteinNFA::levenshtein(m, damerau);
+            let parametric_dfa = ParametricDFA::from_nfa(&lev);
+            for query in ["", "a", "abあ", "bab"].iter() {
+                let dfa = parametric_dfa.build_suffix_dfa(query);
+                for text in test_sample.rights() {
+                    let text_chars: Vec<char> = text.chars().collect();
+                    let expected = (0..=text_chars.len())
+                        .map(|start| {
+                            let suffix: String = text_chars[start..].iter().collect();
+                            let nfa_distance = lev.compute_distance(query, &suffix);
+                            nfa_distance.distance_value()
+                        })
+                        .min()
+                        .unwrap();
+                    assert_eq!(
+                        dfa.eval(text),
+                        make_distance(expected, m),
+                        "{} {}",
+                        query,
+                        text
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_accepting_distance_range() {
+    for m in 0u8..3u8 {
+        let nfa = LevenshteinNFA::levenshtein(m, false);
+        let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+        let dfa = parametric_dfa.build_dfa("abcdef", false);
+        assert_eq!(dfa.accepting_distance_range(), Some(0..=m));
+    }
+}
+
+#[test]
+fn test_first_accepting_state_on_path() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let (offset, state, distance) = dfa.first_accepting_state_on_path("abcd").unwrap();
+    assert_eq!(offset, 2);
+    assert_eq!(distance, Distance::Exact(1));
+    assert_eq!(dfa.distance(state), distance);
+    assert_eq!(dfa.first_accepting_state_on_path("a"), None);
+    assert_eq!(dfa.first_accepting_state_on_path("xyz"), None);
+    let dfa = parametric_dfa.build_dfa("a", false);
+    assert_eq!(
+        dfa.first_accepting_state_on_path("xyz"),
+        Some((0, dfa.initial_state(), Distance::Exact(1)))
+    );
+}
+
+#[test]
+fn test_all_accepting_positions() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    assert_eq!(
+        dfa.all_accepting_positions("abcdxyz"),
+        vec![
+            (2, Distance::Exact(1)),
+            (3, Distance::Exact(0)),
+            (4, Distance::Exact(1))
+        ]
+    );
+    assert!(dfa.all_accepting_positions("xyz").is_empty());
+}
+
+#[test]
+fn test_first_mismatch_byte() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcdef", false);
+    assert_eq!(dfa.first_mismatch_byte("abcdef"), None);
+    assert_eq!(dfa.first_mismatch_byte("abxyef"), Some(3));
+    assert_eq!(dfa.first_mismatch_byte("abcdefgh"), Some(7));
+    assert_eq!(dfa.first_mismatch_byte(""), None);
+}
+
+#[test]
+fn test_common_prefix_states() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcdef", false);
+    assert_eq!(dfa.common_prefix_states(b"abcdef", b"abcxyz"), 3);
+    assert_eq!(dfa.common_prefix_states(b"abc", b"abcdef"), 3);
+    assert_eq!(dfa.common_prefix_states(b"xbc", b"abc"), 0);
+    assert_eq!(dfa.common_prefix_states(b"", b"abc"), 0);
+    assert_eq!(dfa.common_prefix_states(b"xyzxyz", b"uvwuvw"), 6);
+    assert_eq!(dfa.eval("xyzxyz"), Distance::AtLeast(2));
+    assert_eq!(dfa.resume_eval(dfa.initial_state(), b"xyz").0, SINK_STATE);
+    assert_eq!(dfa.resume_eval(dfa.initial_state(), b"uvw").0, SINK_STATE);
+}
+
+#[test]
+fn test_resume_eval() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcdef", false);
+    let previous = b"abcdxx";
+    let next = b"abcdef";
+    let prefix_len = dfa.common_prefix_states(previous, next);
+    let (prefix_state, _) = dfa.resume_eval(dfa.initial_state(), &previous[..prefix_len]);
+    let (state, distance) = dfa.resume_eval(prefix_state, &next[prefix_len..]);
+    assert_eq!(distance, Distance::Exact(0));
+    assert_eq!(dfa.distance(state), dfa.eval(next));
+    assert_eq!(
+        dfa.resume_eval(prefix_state, b""),
+        (prefix_state, dfa.eval("abcd"))
+    );
+}
+
+#[test]
+fn test_eval_prefix_distance() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcdef", false);
+    let prefix_dfa = parametric_dfa.build_dfa("abcdef", true);
+    for &text in [
+        "",
+        "abc",
+        "abcdef",
+        "abcdefxyz",
+        "abdcefghij",
+        "xyzabcdef",
+        "あbcdefあ",
+    ]
+    .iter()
+    {
+        assert_eq!(dfa.eval_prefix_distance(text), prefix_dfa.eval(text));
+    }
+    assert_eq!(dfa.eval_prefix_distance("abcdefxyz"), Distance::Exact(0));
+    assert_eq!(dfa.eval("abcdefxyz"), Distance::AtLeast(3));
+}
+
+struct TestTrie {
+    // For each node, its byte, whether it is terminal, and its children.
+    nodes: Vec<(u8, bool, Vec<usize>)>,
+}
+
+impl TestTrie {
+    fn from_terms(terms: &[&str]) -> TestTrie {
+        let mut nodes: Vec<(u8, bool, Vec<usize>)> = vec![(0u8, false, Vec::new())];
+        for term in terms {
+            let mut node = 0;
+            for &b in term.as_bytes() {
+                let child = nodes[node].2.iter().cloned().find(|&c| nodes[c].0 == b);
+                node = child.unwrap_or_else(|| {
+                    nodes.push((b, false, Vec::new()));
+                    let child = nodes.len() - 1;
+                    nodes[node].2.push(child);
+                    child
+                });
+            }
+            nodes[node].1 = true;
+        }
+        TestTrie { nodes }
+    }
+}
+
+impl Trie for TestTrie {
+    type Node = usize;
+
+    fn get_root(&self) -> usize {
+        0
+    }
+
+    fn children(&self, node: usize) -> Vec<usize> {
+        self.nodes[node].2.clone()
+    }
+
+    fn node_byte(&self, node: usize) -> u8 {
+        self.nodes[node].0
+    }
+
+    fn is_terminal(&self, node: usize) -> bool {
+        self.nodes[node].1
+    }
+}
+
+#[test]
+fn test_iter_accepted_in_trie() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("happy", false);
+    let terms = [
+        "", "hap", "happ", "happy", "happen", "hippy", "sappy", "unhappy", "あppy",
+    ];
+    let trie = TestTrie::from_terms(&terms);
+    let mut accepted: Vec<(usize, Distance)> = dfa.iter_accepted_in_trie(&trie).collect();
+    accepted.sort_by_key(|&(node, _)| node);
+    let mut expected: Vec<(usize, Distance)> = Vec::new();
+    for term in terms.iter() {
+        if let Distance::Exact(d) = dfa.eval(term) {
+            let mut node = 0;
+            for &b in term.as_bytes() {
+                node = trie
+                    .children(node)
+                    .into_iter()
+                    .find(|&c| trie.node_byte(c) == b)
+                    .unwrap();
+            }
+            expected.push((node, Distance::Exact(d)));
+        }
+    }
+    expected.sort_by_key(|&(node, _)| node);
+    assert_eq!(expected.len(), 4);
+    assert_eq!(accepted, expected);
+}
+
+#[test]
+fn test_index_values_and_ids() {
+    let mut index: Index<&str> = Index::new();
+    assert_eq!(index.get_or_allocate(&"a"), 0);
+    assert_eq!(index.get_or_allocate(&"b"), 1);
+    assert_eq!(index.get_or_allocate(&"a"), 0);
+    assert_eq!(index.values().cloned().collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(index.ids().collect::<Vec<u32>>(), vec![0, 1]);
+}
+
+#[test]
+fn test_build_dfa_with_progress() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "abcdefghij".repeat(30);
+    let mut calls: Vec<(usize, usize)> = Vec::new();
+    let dfa = parametric_dfa.build_dfa_with_progress(&query, false, |states_built, max_states| {
+        calls.push((states_built, max_states))
+    });
+    let expected_dfa = parametric_dfa.build_dfa(&query, false);
+    assert_eq!(dfa.num_states(), expected_dfa.num_states());
+    assert_eq!(dfa.eval(&query), Distance::Exact(0));
+    assert!(calls.len() > 1);
+    let &(states_built, max_states) = calls.last().unwrap();
+    assert!(states_built <= dfa.num_states());
+    assert!(states_built <= max_states);
+    for window in calls.windows(2) {
+        assert!(window[0].0 < window[1].0);
+    }
+}
+
+#[test]
+fn test_build_dfa_with_compiler() {
+    use crate::{DFACompiler, Utf8DFABuilder};
+
+    struct CountingCompiler {
+        dfa_builder: Utf8DFABuilder,
+        num_states: usize,
+        num_transitions: usize,
+    }
+
+    impl DFACompiler for CountingCompiler {
+        type Output = (DFA, usize, usize);
+
+        fn begin_state(&mut self, state_id: u32, distance: Distance, default_successor: u32) {
+            self.num_states += 1;
+            self.dfa_builder
+                .begin_state(state_id, distance, default_successor);
+        }
+
+        fn add_char_transition(&mut self, from: u32, c: char, to: u32) {
+            self.num_transitions += 1;
+            self.dfa_builder.add_char_transition(from, c, to);
+        }
+
+        fn set_initial(&mut self, state: u32) {
+            self.dfa_builder.set_initial(state);
+        }
+
+        fn finish(self) -> (DFA, usize, usize) {
+            (
+                self.dfa_builder.finish(),
+                self.num_states,
+                self.num_transitions,
+            )
+        }
+    }
+
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "abcあ";
+    let compiler = CountingCompiler {
+        dfa_builder: Utf8DFABuilder::with_max_num_states(parametric_dfa.num_states() * 5),
+        num_states: 0,
+        num_transitions: 0,
+    };
+    let (dfa, num_states, num_transitions) =
+        parametric_dfa.build_dfa_with_compiler(query, false, compiler);
+    let expected_dfa = parametric_dfa.build_dfa(query, false);
+    assert!(num_states > 0);
+    assert_eq!(num_transitions, num_states * 4);
+    assert_eq!(dfa.num_states(), expected_dfa.num_states());
+    for &text in ["abcあ", "abcd", "abあ", "xbcあ", "", "abcああ"].iter() {
+        assert_eq!(dfa.eval(text), expected_dfa.eval(text));
+    }
+    // A `Utf8DFABuilder` keeps the parametric states, like `build_dfa`.
+    let dfa_builder = Utf8DFABuilder::with_max_num_states(parametric_dfa.num_states() * 5);
+    let dfa = parametric_dfa.build_dfa_with_compiler(query, false, dfa_builder);
+    assert_eq!(dfa.num_states(), expected_dfa.num_states());
+    for state in 0..dfa.num_states() as u32 {
+        assert_eq!(
+            dfa.parametric_state_for_dfa_state(state),
+            expected_dfa.parametric_state_for_dfa_state(state)
+        );
+    }
+    assert!(dfa
+        .parametric_state_for_dfa_state(dfa.initial_state())
+        .is_some());
+}
+
+#[test]
+fn test_nfa_is_damerau() {
+    assert!(LevenshteinNFA::levenshtein(2, true).is_damerau());
+    assert!(!LevenshteinNFA::levenshtein(2, false).is_damerau());
+}
+
+#[test]
+fn test_consuming_distance_with_length_check() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    assert_eq!(
+        dfa.consuming_distance_with_length_check("abd", 3),
+        Distance::Exact(1)
+    );
+    assert_eq!(
+        dfa.consuming_distance_with_length_check("あ", 3),
+        Distance::AtLeast(2)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Text length does not match the expected length")]
+fn test_consuming_distance_with_length_check_panics() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    dfa.consuming_distance_with_length_check("ab", 3);
+}
+
+#[test]
+fn test_eval_range() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let text = b"xxabdxx";
+    assert_eq!(dfa.eval_range(text, 2, 5), Distance::Exact(1));
+    assert_eq!(dfa.eval_range(text, 2, 4), Distance::Exact(1));
+    assert_eq!(dfa.eval_range(text, 0, 7), Distance::AtLeast(2));
+    assert_eq!(dfa.eval_range(text, 7, 7), Distance::AtLeast(2));
+}
+
+#[test]
+#[should_panic(expected = "Range end is larger than the text length")]
+fn test_eval_range_out_of_bounds() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    dfa.eval_range(b"abc", 1, 4);
+}
+
+#[test]
+fn test_serialize_to_writer() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcあd", false);
+    let mut bytes: Vec<u8> = Vec::new();
+    dfa.serialize_to_writer(&mut bytes).unwrap();
+    assert_eq!(bytes, dfa.to_bytes());
+    bytes.extend_from_slice(b"tail");
+    let mut rdr: &[u8] = &bytes[..];
+    let deserialized_dfa = DFA::deserialize_from_reader(&mut rdr).unwrap();
+    assert_eq!(rdr, b"tail");
+    assert_eq!(deserialized_dfa.into_parts(), dfa.into_parts());
+    let mut truncated: &[u8] = &bytes[..100];
+    assert_eq!(
+        DFA::deserialize_from_reader(&mut truncated)
+            .err()
+            .unwrap()
+            .kind(),
+        std::io::ErrorKind::UnexpectedEof
+    );
+    let mut invalid_tag = bytes.clone();
+    invalid_tag[8] = 2;
+    assert_eq!(
+        DFA::deserialize_from_reader(&mut &invalid_tag[..])
+            .err()
+            .unwrap()
+            .kind(),
+        std::io::ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn test_state_to_utf8_description() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("aあ", false);
+    let initial_state = dfa.initial_state();
+    let description = |state: u32| dfa.state_to_utf8_description(state);
+    assert_eq!(description(initial_state).unwrap(), "char boundary");
+    let mut state = dfa.transition(initial_state, b'a');
+    assert_eq!(description(state).unwrap(), "char boundary");
+    for (i, &b) in "あ".as_bytes().iter().enumerate() {
+        if i == 1 {
+            assert_eq!(
+                description(state).unwrap(),
+                "expecting 2 more bytes of a multibyte char"
+            );
+        }
+        state = dfa.transition(state, b);
+    }
+    assert_eq!(dfa.eval("aあ"), Distance::Exact(0));
+    assert_eq!(description(state).unwrap(), "char boundary");
+    let state = dfa.transition(initial_state, 0xc3);
+    assert_eq!(
+        description(state).unwrap(),
+        "expecting 1 more byte of a multibyte char"
+    );
+    assert_eq!(description(dfa.num_states() as u32), None);
+    let dfa = DFA::from_raw_parts(vec![[0u32; 256]], vec![Distance::AtLeast(1)], 0);
+    assert_eq!(dfa.state_to_utf8_description(0), None);
+}
+
+#[test]
+fn test_distance_min_max() {
+    use crate::Distance::{AtLeast, Exact};
+    assert_eq!(Exact(1).min(Exact(2)), Exact(1));
+    assert_eq!(Exact(2).min(AtLeast(1)), AtLeast(1));
+    assert_eq!(AtLeast(3).min(Exact(2)), Exact(2));
+    assert_eq!(AtLeast(3).min(AtLeast(2)), AtLeast(2));
+    assert_eq!(Exact(1).max(Exact(2)), Exact(2));
+    assert_eq!(Exact(2).max(AtLeast(1)), AtLeast(2));
+    assert_eq!(AtLeast(3).max(Exact(2)), AtLeast(3));
+    assert_eq!(AtLeast(3).max(AtLeast(2)), AtLeast(3));
+}
+
+#[test]
+fn test_distance_conversions() {
+    assert_eq!(Option::<u8>::from(Distance::Exact(2)), Some(2));
+    assert_eq!(Option::<u8>::from(Distance::AtLeast(2)), None);
+    assert_eq!(Distance::from(3u8), Distance::Exact(3));
+}
+
+#[test]
+fn test_distance_try_into_u8() {
+    use crate::DistanceNotExact;
+    use std::convert::TryFrom;
+    assert_eq!(u8::try_from(Distance::Exact(2)), Ok(2));
+    assert_eq!(u8::try_from(Distance::AtLeast(3)), Err(DistanceNotExact(3)));
+}
+
+#[test]
+fn test_states_at_distance() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcdef", false);
+    for d in 0..3 {
+        let states = dfa.states_at_distance(d);
+        assert!(!states.is_empty());
+        for state in states {
+            assert_eq!(dfa.distance(state), Distance::Exact(d));
+        }
+    }
+    assert!(dfa.states_at_distance(3).is_empty());
+    let mut state = dfa.initial_state();
+    for &b in b"abcdef" {
+        state = dfa.transition(state, b);
+    }
+    assert!(dfa.states_at_distance(0).contains(&state));
+}
+
+#[test]
+fn test_range_dfa() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_range_dfa("abcdef", 1, 1);
+    assert_eq!(dfa.eval("abcdef"), Distance::AtLeast(0));
+    assert_eq!(dfa.eval("abcde"), Distance::Exact(1));
+    assert_eq!(dfa.eval("abcd"), Distance::AtLeast(2));
+    assert_eq!(dfa.eval("abc"), Distance::AtLeast(3));
+    let dfa = parametric_dfa.build_range_dfa("abcdef", 1, 2);
+    assert_eq!(dfa.eval("abcdef"), Distance::AtLeast(0));
+    assert_eq!(dfa.eval("abcde"), Distance::Exact(1));
+    assert_eq!(dfa.eval("abcd"), Distance::Exact(2));
+    assert_eq!(dfa.eval("abc"), Distance::AtLeast(3));
+}
+
+#[test]
+#[ignore]
+fn test_standard_parametric_dfas_slow() {
+    let standard = ParametricDFA::standard(1, false).unwrap();
+    assert!(std::ptr::eq(
+        standard,
+        ParametricDFA::standard(1, false).unwrap()
+    ));
+    assert_eq!(standard.num_states(), 6);
+    assert_eq!(ParametricDFA::standard(1, true).unwrap().num_states(), 8);
+    assert_eq!(ParametricDFA::standard(2, false).unwrap().num_states(), 31);
+    assert_eq!(ParametricDFA::standard(3, false).unwrap().num_states(), 197);
+    let dfa = ParametricDFA::standard(2, true)
+        .unwrap()
+        .build_dfa("abcdef", false);
+    assert_eq!(dfa.eval("abdcf"), Distance::Exact(2));
+    assert!(ParametricDFA::standard(0, false).is_none());
+    assert!(ParametricDFA::standard(5, true).is_none());
+}
+
+#[test]
+fn test_states_iter() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let states: Vec<(u32, Distance)> = dfa.states_iter().collect();
+    assert_eq!(states.len(), dfa.num_states());
+    for (state, distance) in states {
+        assert_eq!(dfa.distance(state), distance);
+    }
+}
+
+#[test]
+fn test_transitions_from() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let initial_state = dfa.initial_state();
+    let transitions: Vec<(u8, u32)> = dfa.transitions_from(initial_state).collect();
+    assert_eq!(transitions.len(), 256);
+    for (b, dest) in transitions {
+        assert_eq!(dfa.transition(initial_state, b), dest);
+    }
+    assert!(dfa
+        .transitions_from(SINK_STATE)
+        .filter(|&(b, _)| b < 128u8)
+        .all(|(_, dest)| dest == SINK_STATE));
+}
+
+#[test]
+fn test_non_default_transitions_from() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let initial_state = dfa.initial_state();
+    let default_dest = dfa.default_transition(initial_state);
+    assert_eq!(dfa.transition(initial_state, b'x'), default_dest);
+    let non_default_bytes: Vec<u8> = dfa
+        .non_default_transitions_from(initial_state)
+        .filter(|&(b, _)| b < 128u8)
+        .map(|(b, _)| b)
+        .collect();
+    assert_eq!(non_default_bytes, b"ab".to_vec());
+    assert_eq!(dfa.default_transition(SINK_STATE), SINK_STATE);
+}
+
+#[test]
+fn test_compressed_dfa() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "abcdefghijklmnopqrst";
+    let dfa = parametric_dfa.build_dfa(query, false);
+    let compressed_dfa = dfa.compress();
+    assert_eq!(compressed_dfa.num_states(), dfa.num_states());
+    for state in 0..dfa.num_states() as u32 {
+        assert_eq!(compressed_dfa.distance(state), dfa.distance(state));
+        for (b, dest) in dfa.transitions_from(state) {
+            assert_eq!(compressed_dfa.transition(state, b), dest);
+        }
+    }
+    for text in [query, "abcdefghijklmnoqrst", "abcdefあghijklmnopqrst", ""].iter() {
+        assert_eq!(compressed_dfa.eval(text), dfa.eval(text));
+    }
+    let dfa_table_size = dfa.num_states() * 256 * 4;
+    assert!(compressed_dfa.transition_table_size() * 15 < dfa_table_size);
+}
+
+#[test]
+fn test_topological_sort() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcあd", false);
+    let order = dfa.topological_sort().unwrap();
+    assert_eq!(order.len(), dfa.num_states());
+    assert_eq!(order.last(), Some(&SINK_STATE));
+    let mut rank = vec![0; dfa.num_states()];
+    for (i, &state) in order.iter().enumerate() {
+        rank[state as usize] = i;
+    }
+    for &state in &order[..order.len() - 1] {
+        for (_, dest) in dfa.transitions_from(state) {
+            assert!(rank[state as usize] < rank[dest as usize]);
+        }
+    }
+    assert!(parametric_dfa
+        .build_dfa("abc", true)
+        .topological_sort()
+        .is_none());
+}
+
+#[test]
+fn test_parametric_state_for_dfa_state() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "abcあdef";
+    let query_len = query.chars().count();
+    let dfa = parametric_dfa.build_dfa(query, false);
+    for &text in ["abcあ", "abc", "abdef", "xyz", "abcdef"].iter() {
+        let mut state = dfa.initial_state();
+        for &b in text.as_bytes() {
+            state = dfa.transition(state, b);
+        }
+        let parametric_state = dfa.parametric_state_for_dfa_state(state).unwrap();
+        assert_eq!(
+            parametric_state,
+            parametric_dfa.compute_state(ParametricDFA::initial_state(), query, text)
+        );
+        assert_eq!(
+            parametric_dfa.distance(parametric_state, query_len),
+            dfa.distance(state)
+        );
+    }
+    let mut state = dfa.initial_state();
+    state = dfa.transition(state, "あ".as_bytes()[0]);
+    assert_eq!(dfa.parametric_state_for_dfa_state(state), None);
+}
+
+#[test]
+fn test_build_dfa_chunked() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "abcdefghijklmnop";
+    let dfa = parametric_dfa.build_dfa(query, false);
+    let texts = [
+        "abcdefghijklmnop",
+        "abcdefgXijklmnop",
+        "abcdfghijklmnop",
+        "abXcdefghijklmXnop",
+        "abcdefghijklmno",
+        "abcdefghijklm",
+        "Xbcdefghijklmnop",
+        "",
+    ];
+    for &chunk_size in [1, 4, 5, 16, 100].iter() {
+        let chunked_dfa = parametric_dfa.build_dfa_chunked(query, chunk_size);
+        for &text in texts.iter() {
+            let distance = dfa.eval(text).distance_value();
+            let chunked_distance = chunked_dfa.eval(text).distance_value();
+            if chunk_size >= query.len() {
+                assert_eq!(chunked_distance, distance);
+            } else {
+                assert!(chunked_distance >= distance);
+            }
+        }
+    }
+    let chunked_dfa = parametric_dfa.build_dfa_chunked(query, 4);
+    for &text in texts.iter() {
+        assert_eq!(chunked_dfa.eval(text), dfa.eval(text));
+    }
+}
+
+#[test]
+fn test_eval_with_limit() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    assert_eq!(dfa.eval_with_limit("abc", 3), Some(Distance::Exact(0)));
+    assert_eq!(dfa.eval_with_limit("abc", 2), None);
+    assert_eq!(dfa.eval_with_limit("", 0), Some(Distance::AtLeast(2)));
+    assert_eq!(
+        dfa.eval_with_limit("xyzxyzxyz", 4),
+        Some(Distance::AtLeast(2))
+    );
+}
+
+#[test]
+fn test_dfa_to_bytes_from_bytes() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let bytes = dfa.to_bytes();
+    let deserialized = DFA::from_bytes(&bytes).unwrap();
+    assert_eq!(deserialized.num_states(), dfa.num_states());
+    for &text in ["abc", "ab", "abcd", "xyz", ""].iter() {
+        assert_eq!(deserialized.eval(text), dfa.eval(text));
+    }
+    assert_eq!(
+        DFA::from_bytes(&bytes[..bytes.len() - 1]).err(),
+        Some(InvalidDFA::UnexpectedEndOfInput)
+    );
+    let mut trailing = bytes.clone();
+    trailing.push(0u8);
+    assert_eq!(
+        DFA::from_bytes(&trailing).err(),
+        Some(InvalidDFA::TrailingBytes)
+    );
+    assert_eq!(
+        DFA::from_bytes(&[255u8; 8]).err(),
+        Some(InvalidDFA::UnexpectedEndOfInput)
+    );
+    // The transition of the sink state on `b'a'` is at offset
+    // 8 (header) + 2 * num_states (distances) + 4 * b'a'.
+    let mut sink_leaving = bytes.clone();
+    let offset = 8 + 2 * dfa.num_states() + 4 * usize::from(b'a');
+    sink_leaving[offset..offset + 4].copy_from_slice(&dfa.initial_state().to_le_bytes());
+    assert_eq!(
+        DFA::from_bytes(&sink_leaving).err(),
+        Some(InvalidDFA::NonAbsorbingSinkState)
+    );
+}
+
+#[test]
+fn test_build_dfa_with_unit_error_model() {
+    let strings = [
+        "",
+        "a",
+        "ab",
+        "abc",
+        "bac",
+        "abcd",
+        "acbd",
+        "xyz",
+        "ありがとう",
+        "あがとう",
+    ];
+    for m in 0u8..3u8 {
+        let nfa = LevenshteinNFA::levenshtein(m, false);
+        let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+        for &query in strings.iter() {
+            let dfa = parametric_dfa.build_dfa_with_error_model(query, &UnitErrorModel);
+            for &text in strings.iter() {
+                let expected = levenshtein::levenshtein(query, text) as u8;
+                assert_eq!(dfa.eval(text), make_distance(expected, m));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_build_dfa_with_error_model() {
+    struct KeyboardErrorModel;
+
+    impl ErrorModel for KeyboardErrorModel {
+        fn cost(&self, from: char, to: char) -> Option<u8> {
+            let rows = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+            let position = |c: char| {
+                rows.iter()
+                    .enumerate()
+                    .find_map(|(row, keys)| keys.find(c).map(|col| (row as i32, col as i32)))
+            };
+            match (position(from), position(to)) {
+                (Some((from_row, from_col)), Some((to_row, to_col)))
+                    if (from_row - to_row).abs() <= 1 && (from_col - to_col).abs() <= 1 =>
+                {
+                    Some(1)
+                }
+                _ => Some(2),
+            }
+        }
+
+        fn cost_insert(&self, _c: char) -> Option<u8> {
+            Some(2)
+        }
+
+        fn cost_delete(&self, c: char) -> Option<u8> {
+            if c == 'c' {
+                None
+            } else {
+                Some(2)
+            }
+        }
+
+        fn alphabet(&self) -> Vec<char> {
+            "qwertyuiopasdfghjklzxcvbnm".chars().collect()
+        }
+    }
+
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa_with_error_model("cat", &KeyboardErrorModel);
+    assert_eq!(dfa.eval("cat"), Distance::Exact(0));
+    assert_eq!(dfa.eval("xat"), Distance::Exact(1));
+    assert_eq!(dfa.eval("xar"), Distance::Exact(2));
+    assert_eq!(dfa.eval("pat"), Distance::Exact(2));
+    assert_eq!(dfa.eval("at"), Distance::AtLeast(3));
+    assert_eq!(dfa.eval("ca"), Distance::Exact(2));
+    assert_eq!(dfa.eval("cart"), Distance::Exact(2));
+    assert_eq!(dfa.eval("c€t"), Distance::Exact(2));
+}
+
+// Checks that `ops` transform `query` into `text`, or into a prefix of `text`
+// if `prefix` is true, and returns the number of edits.
+fn replay_alignment(query: &str, text: &str, ops: &[AlignOp], prefix: bool) -> u8 {
+    let query: Vec<char> = query.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut i, mut j) = (0, 0);
+    let mut num_edits = 0u8;
+    for op in ops {
+        assert_eq!((op.query_pos, op.text_pos), (i, j));
+        match op.kind {
+            AlignKind::Match => assert_eq!(query[i], text[j]),
+            AlignKind::Substitute => assert_ne!(query[i], text[j]),
+            AlignKind::Transpose => {
+                assert_eq!((query[i], query[i + 1]), (text[j + 1], text[j]));
+            }
+            AlignKind::Insert | AlignKind::Delete => {}
+        }
+        let (di, dj) = match op.kind {
+            AlignKind::Match | AlignKind::Substitute => (1, 1),
+            AlignKind::Transpose => (2, 2),
+            AlignKind::Insert => (0, 1),
+            AlignKind::Delete => (1, 0),
+        };
+        i += di;
+        j += dj;
+        if op.kind != AlignKind::Match {
+            num_edits += 1;
+        }
+    }
+    assert_eq!(i, query.len());
+    if prefix {
+        assert!(j <= text.len());
+    } else {
+        assert_eq!(j, text.len());
+    }
+    num_edits
+}
+
+#[test]
+fn test_dfa_align() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("kitten", false);
+    let alignment = dfa.align("sittin").unwrap();
+    assert_eq!(alignment.distance, Distance::Exact(2));
+    let kinds: Vec<AlignKind> = alignment.ops.iter().map(|op| op.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            AlignKind::Substitute,
+            AlignKind::Match,
+            AlignKind::Match,
+            AlignKind::Match,
+            AlignKind::Substitute,
+            AlignKind::Match,
+        ]
+    );
+    assert_eq!(
+        replay_alignment("kitten", "kiten", &dfa.align("kiten").unwrap().ops, false),
+        1
+    );
+    // Without transpositions, swapping two chars takes two edits.
+    let alignment = dfa.align("iktten").unwrap();
+    assert_eq!(alignment.distance, Distance::Exact(2));
+    assert!(alignment
+        .ops
+        .iter()
+        .all(|op| op.kind != AlignKind::Transpose));
+
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("kitten", false);
+    let alignment = dfa.align("iktten").unwrap();
+    assert_eq!(alignment.distance, Distance::Exact(1));
+    assert_eq!(
+        alignment.ops[0],
+        AlignOp {
+            query_pos: 0,
+            text_pos: 0,
+            kind: AlignKind::Transpose,
+        }
+    );
+    assert_eq!(alignment.ops.len(), 5);
+
+    let dfa = parametric_dfa.build_dfa("kitten", true);
+    let alignment = dfa.align("kittenxyz").unwrap();
+    assert_eq!(alignment.distance, Distance::Exact(0));
+    assert_eq!(alignment.ops.len(), 6);
+
+    let dfa = parametric_dfa.build_suffix_dfa("kitten");
+    assert_eq!(dfa.align("kitten"), None);
+}
+
+#[test]
+fn test_dfa_align_follows_edit_model() {
+    let strings = combinations(&['a', 'b', 'c'], 4);
+    for &transposition in [false, true].iter() {
+        let nfa = LevenshteinNFA::levenshtein(2, transposition);
+        let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+        for &prefix in [false, true].iter() {
+            for query in &strings {
+                let dfa = parametric_dfa.build_dfa(query, prefix);
+                for text in &strings {
+                    let alignment = dfa.align(text).unwrap();
+                    let num_edits = replay_alignment(query, text, &alignment.ops, prefix);
+                    match alignment.distance {
+                        Distance::Exact(d) => assert_eq!(num_edits, d),
+                        _ => assert!(num_edits > 2),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_levenshtein_nfa_debug() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    assert_eq!(
+        format!("{:?}", nfa),
+        "LevenshteinNFA { max_distance: 2, damerau: true }"
+    );
+}
+
+#[test]
+fn test_levenshtein_nfa_eq() {
+    assert_eq!(
+        LevenshteinNFA::levenshtein(2, true),
+        LevenshteinNFA::levenshtein(2, true)
+    );
+    assert_ne!(
+        LevenshteinNFA::levenshtein(2, true),
+        LevenshteinNFA::levenshtein(2, false)
+    );
+    assert_ne!(
+        LevenshteinNFA::levenshtein(1, false),
+        LevenshteinNFA::levenshtein(2, false)
+    );
+}
+
+#[test]
+fn test_max_states_estimate() {
+    for &damerau in [false, true].iter() {
+        for d in 0u8..3u8 {
+            let nfa = LevenshteinNFA::levenshtein(d, damerau);
+            let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+            assert!(parametric_dfa.num_states() <= nfa.max_states_estimate());
+        }
+    }
+    assert_eq!(
+        LevenshteinNFA::levenshtein(1, false).max_states_estimate(),
+        6
+    );
+    assert_eq!(
+        LevenshteinNFA::levenshtein(2, false).max_states_estimate(),
+        31
+    );
+    assert_eq!(
+        LevenshteinNFA::levenshtein(6, false).max_states_estimate(),
+        usize::MAX
+    );
+}
+
+#[test]
+#[ignore]
+fn test_max_states_estimate_damerau_slow() {
+    for d in 3u8..5u8 {
+        let nfa = LevenshteinNFA::levenshtein(d, true);
+        let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+        assert_eq!(parametric_dfa.num_states(), nfa.max_states_estimate());
+    }
+}
+
+#[test]
+fn test_nfa_state_new() {
+    let state = NFAState::new(3, 1, true);
+    assert_eq!(state.offset(), 3);
+    assert_eq!(state.distance(), 1);
+    assert!(state.in_transpose());
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    assert_eq!(nfa.initial_states().states(), &[NFAState::new(0, 0, false)]);
+}
+
+#[test]
+fn test_multistate_add() {
+    let mut multistate = MultiState::empty();
+    assert!(multistate.add(NFAState::new(1, 1, false)));
+    // Implied by the state (1, 1).
+    assert!(!multistate.add(NFAState::new(2, 2, false)));
+    assert!(multistate.add(NFAState::new(3, 1, false)));
+    // Implies the state (1, 1).
+    assert!(multistate.add(NFAState::new(1, 0, false)));
+    multistate.normalize();
+    assert_eq!(
+        multistate.states(),
+        &[NFAState::new(0, 0, false), NFAState::new(2, 1, false)]
+    );
+}
+
+#[test]
+fn test_nfa_state_dominates() {
+    let state = NFAState::new(1, 1, false);
+    assert!(state.dominates(state));
+    assert!(state.dominates(NFAState::new(2, 2, false)));
+    assert!(state.dominates(NFAState::new(0, 2, false)));
+    assert!(!state.dominates(NFAState::new(3, 2, false)));
+    assert!(!state.dominates(NFAState::new(1, 0, false)));
+    assert!(!state.dominates(NFAState::new(2, 2, true)));
+    assert!(NFAState::new(1, 1, true).dominates(NFAState::new(2, 2, true)));
+}
+
+#[test]
+fn test_multistate_into_iter() {
+    let mut multistate = MultiState::empty();
+    multistate.add(NFAState::new(0, 1, false));
+    multistate.add(NFAState::new(3, 0, false));
+    let mut offsets = Vec::new();
+    for state in &multistate {
+        offsets.push(state.offset());
+    }
+    assert_eq!(offsets, vec![0, 3]);
+    let states: Vec<NFAState> = multistate.into_iter().collect();
+    assert_eq!(
+        states,
+        vec![NFAState::new(0, 1, false), NFAState::new(3, 0, false)]
+    );
+}
+
+#[test]
+fn test_multistate_debug() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    assert_eq!(
+        format!("{:?}", nfa.initial_states()),
+        "MultiState { states: [NFAState { offset: 0, distance: 0, in_transpose: false }] }"
+    );
+}
+
+#[test]
+fn test_dynamic_levenshtein_distance() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("寿司", false);
+    let mut distance = DynamicLevenshteinDistance::new(dfa);
+    assert_eq!(distance.distance(), Distance::Exact(2));
+    assert_eq!(distance.feed_str("寿"), Distance::Exact(1));
+    let mut last_distance = distance.distance();
+    for &b in "司".as_bytes() {
+        last_distance = distance.feed(b);
+    }
+    assert_eq!(last_distance, Distance::Exact(0));
+    assert_eq!(distance.feed_str("は"), Distance::Exact(1));
+    distance.reset();
+    assert_eq!(distance.feed_str("寿司"), Distance::Exact(0));
+    let dfa = distance.into_dfa();
+    assert_eq!(dfa.eval("寿司"), Distance::Exact(0));
+}
+
+#[test]
+fn test_dfa_shrink_to_fit() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let mut dfa = parametric_dfa.build_dfa("abc", false);
+    dfa.shrink_to_fit();
+    assert_eq!(dfa.eval("abc"), Distance::Exact(0));
+    assert_eq!(dfa.eval("abd"), Distance::Exact(1));
+    assert_eq!(dfa.eval("xyz"), Distance::AtLeast(2));
+}
+
+#[test]
+fn test_parametric_dfa_shrink_to_fit() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let mut parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let num_states = parametric_dfa.num_states();
+    parametric_dfa.shrink_to_fit();
+    assert_eq!(parametric_dfa.num_states(), num_states);
+    let dfa = parametric_dfa.build_dfa("abcd", false);
+    assert_eq!(dfa.eval("abdc"), Distance::Exact(1));
+}
+
+#[test]
+fn test_builder_max_distance() {
+    for max_distance in 0u8..3u8 {
+        let builder = LevenshteinAutomatonBuilder::new(max_distance, false);
+        assert_eq!(builder.max_distance(), max_distance);
+        assert_eq!(builder.parametric_dfa().max_distance(), max_distance);
+    }
+}
+
+#[test]
+fn test_builder_transposition_cost_one() {
+    for &transposition_cost_one in [false, true].iter() {
+        let builder = LevenshteinAutomatonBuilder::new(1, transposition_cost_one);
+        assert_eq!(builder.transposition_cost_one(), transposition_cost_one);
+    }
+}
+
+#[test]
+fn test_compile_dfa_from_parametric() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    for &prefix in [false, true].iter() {
+        let dfa = compile_dfa_from_parametric(&parametric_dfa, "abc", prefix);
+        let expected_dfa = parametric_dfa.build_dfa("abc", prefix);
+        assert_eq!(dfa.num_states(), expected_dfa.num_states());
+        for &text in ["abc", "abd", "abcdef", "xyz"].iter() {
+            assert_eq!(dfa.eval(text), expected_dfa.eval(text));
+        }
+    }
+}
+
+#[test]
+fn test_eval_str() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    for &text in ["abc", "abd", "xyz", "", "寿司"].iter() {
+        assert_eq!(dfa.eval_str(text), dfa.eval(text));
+        assert_eq!(dfa.eval_string(&text.to_string()), dfa.eval(text));
+        assert_eq!(dfa.eval_slice(text.as_bytes()), dfa.eval(text));
+    }
+}
+
+#[test]
+fn test_eval_all() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    assert_eq!(
+        dfa.eval_all(vec!["abc", "abd", "xyz"]),
+        vec![Distance::Exact(0), Distance::Exact(1), Distance::AtLeast(2)]
+    );
+    assert!(dfa.eval_all(Vec::new()).is_empty());
+}
+
+#[test]
+fn test_eval_count_matches() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let texts = ["abc", "abd", "xyz", "ab", "abcde", "", "xbc"];
+    let expected = dfa
+        .eval_all(texts.iter().cloned())
+        .iter()
+        .filter(|d| matches!(d, Distance::Exact(_)))
+        .count();
+    assert_eq!(expected, 4);
+    assert_eq!(dfa.eval_count_matches(texts.iter().cloned()), expected);
+}
+
+#[test]
+fn test_state_id_type() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("Levenshtein", false);
+    assert!(dfa.num_states() <= 256);
+    assert_eq!(dfa.state_id_type(), StateIdType::U8);
+    let dfa = parametric_dfa.build_dfa(&"Levenshtein".repeat(10), false);
+    assert!(dfa.num_states() > 256);
+    assert_eq!(dfa.state_id_type(), StateIdType::U16);
+}
+
+#[test]
+fn test_into_owned() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let owned_dfa = parametric_dfa.build_dfa("abc", false).into_owned();
+    let cloned_dfa = owned_dfa.clone();
+    assert_eq!(owned_dfa.eval("abd"), Distance::Exact(1));
+    assert_eq!(cloned_dfa.eval("abd"), Distance::Exact(1));
+    let handle = std::thread::spawn(move || cloned_dfa.eval("xyz"));
+    assert_eq!(handle.join().unwrap(), Distance::AtLeast(2));
+}
+
+#[test]
+fn test_to_u16_compact() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let query = "Levenshtein".repeat(10);
+    let dfa = parametric_dfa.build_dfa(&query, false);
+    assert_eq!(dfa.state_id_type(), StateIdType::U16);
+    let num_states = dfa.num_states();
+    let texts = [
+        query.clone(),
+        query.replace("tein", "tain"),
+        "Levenshtein".to_string(),
+    ];
+    let distances: Vec<Distance> = texts.iter().map(|text| dfa.eval(text)).collect();
+    let compact_dfa = dfa.to_u16_compact().unwrap();
+    assert_eq!(compact_dfa.num_states(), num_states);
+    for (text, &distance) in texts.iter().zip(distances.iter()) {
+        assert_eq!(compact_dfa.eval(text), distance);
+    }
+    let dfa = compact_dfa.into_dfa();
+    assert_eq!(dfa.num_states(), num_states);
+    for (text, &distance) in texts.iter().zip(distances.iter()) {
+        assert_eq!(dfa.eval(text), distance);
+    }
+    assert_eq!(
+        CompactDFA::<u8>::from_dfa(dfa).err(),
+        Some(OverflowError(num_states))
+    );
+}
+
+#[test]
+fn test_transition_stats() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("Levenshtein", false);
+    let stats = dfa.transition_stats();
+    assert_eq!(stats.total_transitions, dfa.num_states() * 256);
+    assert_eq!(stats.total_transitions, 22_016);
+    assert_eq!(stats.sink_transitions, 6_979);
+    assert_eq!(stats.non_default_transitions, 3_026);
+    assert!((stats.density - 0.683).abs() < 0.001);
+}
+
+#[test]
+fn test_byte_equivalence_classes() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abca", false);
+    let classes = dfa.byte_equivalence_classes();
+    let mut bytes: Vec<u8> = classes.iter().flatten().cloned().collect();
+    bytes.sort();
+    assert_eq!(bytes, (0..=255u8).collect::<Vec<u8>>());
+    assert!(classes.iter().any(|class| class == &vec![b'a']));
+    assert!(classes[0].contains(&b'x') && classes[0].contains(&b'z'));
+    for class in &classes {
+        for state in 0..dfa.num_states() as u32 {
+            let dest = dfa.transition(state, class[0]);
+            assert!(class.iter().all(|&b| dfa.transition(state, b) == dest));
+        }
+    }
+    for (class, next_class) in classes.iter().zip(classes.iter().skip(1)) {
+        assert!(class[0] < next_class[0]);
+    }
+}
+
+#[test]
+fn test_remap_to_byte_classes() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcdef", false);
+    let mut classes = [0u8; 256];
+    for (class_id, class) in dfa.byte_equivalence_classes().iter().enumerate() {
+        for &b in class {
+            classes[b as usize] = class_id as u8;
+        }
+    }
+    let class_dfa = dfa.remap_to_byte_classes(&classes).unwrap();
+    assert_eq!(class_dfa.num_states(), dfa.num_states());
+    assert_eq!(class_dfa.num_classes(), 10);
+    for state in 0..dfa.num_states() as u32 {
+        assert_eq!(class_dfa.distance(state), dfa.distance(state));
+        for b in 0..=255u8 {
+            assert_eq!(class_dfa.transition(state, b), dfa.transition(state, b));
+        }
+    }
+    for &text in ["abcdef", "abdcef", "abcf", "xyz", "abcdあef"].iter() {
+        assert_eq!(class_dfa.eval(text), dfa.eval(text));
+    }
+    // `b'a'` and `b'b'` lead to different states from the initial state.
+    classes[b'b' as usize] = classes[b'a' as usize];
+    assert_eq!(
+        dfa.remap_to_byte_classes(&classes).err(),
+        Some(ByteClassError {
+            state: dfa.initial_state(),
+            byte: b'b',
+        })
+    );
+}
+
+#[test]
+fn test_count_states_with_distance() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let counts: Vec<usize> = (0..3).map(|d| dfa.count_states_with_distance(d)).collect();
+    assert_eq!(counts[2], 0);
+    let num_accepting_states = dfa
+        .states_iter()
+        .filter(|(_, distance)| matches!(distance, Distance::Exact(_)))
+        .count();
+    assert_eq!(counts[0] + counts[1], num_accepting_states);
+    assert_eq!(dfa.count_states_with_distance(0), 1);
+}
+
+#[test]
+fn test_distance_histogram() {
+    let nfa = LevenshteinNFA::levenshtein(2, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abcdef", false);
+    let histogram = dfa.distance_histogram();
+    assert_eq!(histogram.len(), 4);
+    assert_eq!(histogram.iter().sum::<usize>(), dfa.num_states());
+    for d in 0..3u8 {
+        assert_eq!(histogram[d as usize], dfa.count_states_with_distance(d));
+    }
+    assert!(histogram[3] >= 1);
+}
+
+#[test]
+fn test_transition_graph() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let graph = dfa.transition_graph();
+    assert_eq!(graph.len(), dfa.num_states() * 256);
+    for &(from, b, to) in &graph {
+        assert_eq!(dfa.transition(from, b), to);
+    }
+    assert_eq!(graph[0], (0, 0, SINK_STATE));
+    assert_eq!(graph[256 + 1], (1, 1, dfa.transition(1, 1)));
+}
+
+#[test]
+fn test_condensed_transition_graph() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let graph = dfa.condensed_transition_graph();
+    assert_eq!(graph[0], (0, (0..=255u8).collect(), SINK_STATE));
+    let initial_state = dfa.initial_state();
+    let initial_edges: Vec<&(u32, Vec<u8>, u32)> = graph
+        .iter()
+        .filter(|(from, _, _)| *from == initial_state)
+        .collect();
+    assert!(initial_edges.len() <= 8);
+    assert!(initial_edges
+        .iter()
+        .any(|(_, bytes, _)| bytes == &vec![b'a']));
+    let mut num_transitions = 0;
+    for (from, bytes, to) in &graph {
+        for &b in bytes {
+            assert_eq!(dfa.transition(*from, b), *to);
+        }
+        num_transitions += bytes.len();
+    }
+    assert_eq!(num_transitions, dfa.num_states() * 256);
+}
+
+#[test]
+fn test_count_unique_destination_states() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("", false);
+    // The initial state is not the destination of any transition.
+    assert_eq!(dfa.count_unique_destination_states(), dfa.num_states() - 2);
+    for &query in ["abc", "寿司"].iter() {
+        let dfa = parametric_dfa.build_dfa(query, false);
+        let num_destinations = dfa.count_unique_destination_states();
+        assert!(num_destinations > 0);
+        assert!(num_destinations < dfa.num_states());
+    }
+}
+
+#[test]
+fn test_dfa_into_parts_from_parts() {
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let num_states = dfa.num_states();
+    let DFAParts {
+        transitions,
+        distances,
+        initial_state,
+    } = dfa.into_parts();
+    assert_eq!(transitions.len(), num_states);
+    assert_eq!(distances.len(), num_states);
+    let dfa = DFA::from_parts(DFAParts {
+        transitions,
+        distances,
+        initial_state,
+    });
+    assert!(dfa.validate().is_ok());
+    assert_eq!(dfa.eval("abd"), Distance::Exact(1));
+}
+
+#[test]
+fn test_dfa_validate() {
+    let distances = vec![Distance::AtLeast(2), Distance::Exact(0)];
+    assert!(
+        DFA::from_raw_parts(vec![[0u32; 256]; 2], distances.clone(), 1)
+            .validate()
+            .is_ok()
+    );
+    assert_eq!(
+        DFA::from_raw_parts(vec![[0u32; 256]], distances.clone(), 0).validate(),
+        Err(InvalidDFA::NumStatesMismatch {
+            num_transition_rows: 1,
+            num_distances: 2
+        })
+    );
+    assert_eq!(
+        DFA::from_raw_parts(Vec::new(), Vec::new(), 0).validate(),
+        Err(InvalidDFA::NoStates)
+    );
+    assert_eq!(
+        DFA::from_raw_parts(vec![[0u32; 256]; 2], distances.clone(), 2).validate(),
+        Err(InvalidDFA::InvalidState(2))
+    );
+    assert_eq!(
+        DFA::from_raw_parts(vec![[3u32; 256]; 2], distances, 1).validate(),
+        Err(InvalidDFA::InvalidState(3))
+    );
+    assert_eq!(
+        DFA::from_raw_parts(vec![[0u32; 256]], vec![Distance::Exact(0)], 0).validate(),
+        Err(InvalidDFA::AcceptingSinkState)
+    );
+    let mut transitions = vec![[0u32; 256]; 2];
+    transitions[0][b'a' as usize] = 1;
+    assert_eq!(
+        DFA::from_raw_parts(transitions, vec![Distance::AtLeast(1); 2], 1).validate(),
+        Err(InvalidDFA::NonAbsorbingSinkState)
+    );
+    let parametric_dfa = ParametricDFA::from_nfa(&LevenshteinNFA::levenshtein(1, true));
+    for dfa in [
+        parametric_dfa.build_dfa("abあc", false),
+        parametric_dfa.build_dfa("abあc", true),
+        parametric_dfa.build_suffix_dfa("abあc"),
+        parametric_dfa.build_dfa_with_error_model("abあc", &UnitErrorModel),
+    ]
+    .iter()
+    {
+        assert_eq!(dfa.validate(), Ok(()));
+    }
+}
+
+#[test]
+fn test_dfa_is_total() {
+    let nfa = LevenshteinNFA::levenshtein(2, true);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    assert!(parametric_dfa.build_dfa("abcあd", false).is_total());
+    assert!(parametric_dfa.build_dfa("abcあd", true).is_total());
+    assert!(parametric_dfa.build_suffix_dfa("abc").is_total());
+    let distances = vec![Distance::AtLeast(1), Distance::Exact(0)];
+    let mut transitions = vec![[0u32; 256]; 2];
+    assert!(DFA::from_raw_parts(transitions.clone(), distances.clone(), 1).is_total());
+    transitions[1][b'a' as usize] = 2;
+    assert!(!DFA::from_raw_parts(transitions, distances, 1).is_total());
+}
+
+#[cfg(feature = "c_api")]
+#[test]
+fn test_ffi() {
+    use crate::ffi::{lev_dfa_build, lev_dfa_eval, lev_dfa_free, lev_dfa_is_exact};
+    use std::ffi::CString;
+    use std::os::raw::c_char;
+
+    let query = CString::new("Levenshtein").unwrap();
+    unsafe {
+        let dfa = lev_dfa_build(query.as_ptr(), 1, false);
+        assert!(!dfa.is_null());
+        let eval = |text: &str| lev_dfa_eval(dfa, text.as_ptr() as *const c_char, text.len());
+        assert_eq!(eval("Levenshtain"), 1);
+        assert!(lev_dfa_is_exact(eval("Levenshtain")));
+        assert!(!lev_dfa_is_exact(eval("Hello")));
+        assert_eq!(lev_dfa_eval(dfa, std::ptr::null(), 0), 2 | 0x80);
+        lev_dfa_free(dfa);
+        let invalid_utf8 = CString::new(vec![0xffu8]).unwrap();
+        assert!(lev_dfa_build(invalid_utf8.as_ptr(), 1, false).is_null());
+        assert!(lev_dfa_build(query.as_ptr(), 0, false).is_null());
+        assert!(lev_dfa_build(query.as_ptr(), 5, true).is_null());
+        let dfa = lev_dfa_build(query.as_ptr(), 4, true);
+        assert!(!dfa.is_null());
+        lev_dfa_free(dfa);
+        lev_dfa_free(std::ptr::null_mut());
+    }
+}
+
+#[cfg(feature = "regex_automata")]
+#[test]
+fn test_regex_automata_search() {
+    use regex_automata::dfa::Automaton;
+    use regex_automata::{HalfMatch, Input};
+
+    let nfa = LevenshteinNFA::levenshtein(1, false);
+    let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+    let dfa = parametric_dfa.build_dfa("abc", false);
+    let search = |haystack: &str| dfa.try_search_fwd(&Input::new(haystack)).unwrap();
+    assert_eq!(search("abc"), Some(HalfMatch::must(0, 3)));
+    assert_eq!(search("abdxyz"), Some(HalfMatch::must(0, 3)));
+    assert_eq!(search("abcdxyz"), Some(HalfMatch::must(0, 4)));
+    assert_eq!(search("xyz"), None);
+    assert!(!dfa.has_empty());
+    let dfa = parametric_dfa.build_dfa("a", false);
+    assert!(dfa.has_empty());
+    assert_eq!(
+        dfa.try_search_fwd(&Input::new("")).unwrap(),
+        Some(HalfMatch::must(0, 0))
+    );
+    assert_eq!(
+        dfa.try_search_fwd(&Input::new("xyz")).unwrap(),
+        Some(HalfMatch::must(0, 1))
+    );
+    assert!(!dfa.is_utf8());
+    assert_eq!(
+        dfa.try_search_fwd(&Input::new(&[0x80u8][..])).unwrap(),
+        Some(HalfMatch::must(0, 1))
+    );
+}
+
+#[cfg(feature = "casefold")]
+#[test]
+fn test_build_dfa_unicode_casefold() {
+    use caseless::default_case_fold_str;
+
+    let lev_automaton_builder = LevenshteinAutomatonBuilder::new(1, false);
+    let dfa = lev_automaton_builder.build_dfa_unicode_casefold("STRASSE");
+    assert_eq!(
+        dfa.eval(default_case_fold_str("straße")),
+        Distance::Exact(0)
+    );
+    assert_eq!(
+        dfa.eval(default_case_fold_str("Strase")),
+        Distance::Exact(1)
+    );
+    let dfa = lev_automaton_builder.build_dfa_unicode_casefold("Straße");
+    assert_eq!(
+        dfa.eval(default_case_fold_str("STRASSE")),
+        Distance::Exact(0)
+    );
+    assert_eq!(dfa.eval("straße"), Distance::AtLeast(2));
+    let dfa = lev_automaton_builder.build_dfa_unicode_casefold("ΟΔΥΣΣΕΥΣ");
+    assert_eq!(
+        dfa.eval(default_case_fold_str("Οδυσσεύς")),
+        Distance::Exact(1)
+    );
+    assert_eq!(
+        dfa.eval(default_case_fold_str("οδυσσευς")),
+        Distance::Exact(0)
+    );
+}
+
+#[cfg(feature = "unicode_normalization")]
+#[test]
+fn test_build_dfa_unicode_decomposed() {
+    use unicode_normalization::UnicodeNormalization;
+
+    let lev_automaton_builder = LevenshteinAutomatonBuilder::new(1, false);
+    let composed = "caf\u{e9}";
+    let decomposed = "cafe\u{301}";
+    let dfa = lev_automaton_builder.build_dfa_unicode_decomposed(composed);
+    assert_eq!(
+        dfa.num_states(),
+        lev_automaton_builder
+            .build_dfa_unicode_decomposed(decomposed)
+            .num_states()
+    );
+    assert_eq!(dfa.eval(decomposed), Distance::Exact(0));
+    assert_eq!(dfa.eval(composed.nfd().to_string()), Distance::Exact(0));
+    assert_eq!(dfa.eval("cafe"), Distance::Exact(1));
+    assert_eq!(dfa.eval(composed), Distance::AtLeast(2));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_from_nfa_parallel() {
+    for &damerau in [false, true].iter() {
+        for m in 0u8..3u8 {
+            let nfa = LevenshteinNFA::levenshtein(m, damerau);
+            let parametric_dfa = ParametricDFA::from_nfa(&nfa);
+            let parallel_parametric_dfa = ParametricDFA::from_nfa_parallel(&nfa);
+            assert_eq!(
+                parallel_parametric_dfa.num_states(),
+                parametric_dfa.num_states()
+            );
+            let dfa = parametric_dfa.build_dfa("abcdef", false);
+            let parallel_dfa = parallel_parametric_dfa.build_dfa("abcdef", false);
+            assert_eq!(parallel_dfa.num_states(), dfa.num_states());
+            for &text in ["abcdef", "abdcef", "abcf", "xyz"].iter() {
+                assert_eq!(parallel_dfa.eval(text), dfa.eval(text));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+fn parametric_dfas() -> &'static [ParametricDFA] {
+    use std::sync::OnceLock;
+    static PARAMETRIC_DFAS: OnceLock<Vec<ParametricDFA>> = OnceLock::new();
+    PARAMETRIC_DFAS.get_or_init(|| {
+        (0u8..4u8)
+            .map(|m| ParametricDFA::from_nfa(&LevenshteinNFA::levenshtein(m, false)))
+            .collect()
+    })
+}
+
+#[cfg(feature = "proptest")]
+proptest::proptest! {
+    #[test]
+    fn proptest_dfa_eval(left in "[abcあ❤]{0,10}", right in "[abcあ❤]{0,10}") {
+        let expected = levenshtein::levenshtein(&left, &right) as u8;
+        for (m, parametric_dfa) in parametric_dfas().iter().enumerate() {
+            let dfa = parametric_dfa.build_dfa(&left, false);
+            proptest::prop_assert_eq!(dfa.eval(&right), make_distance(expected, m as u8));
+        }
+    }
+}
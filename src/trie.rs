@@ -0,0 +1,66 @@
+use super::{Distance, DFA, SINK_STATE};
+
+/// A trie whose edges are labelled with bytes, that can be searched
+/// with [DFA::iter_accepted_in_trie(...)](./struct.DFA.html#method.iter_accepted_in_trie).
+///
+/// Each node but the root is labelled with the byte of the edge leading to it.
+pub trait Trie {
+    /// Identifier of a node of the trie.
+    type Node: Copy;
+
+    /// Returns the root of the trie.
+    fn get_root(&self) -> Self::Node;
+
+    /// Returns the children of `node`.
+    fn children(&self, node: Self::Node) -> Vec<Self::Node>;
+
+    /// Returns the byte of the edge leading to `node`.
+    ///
+    /// It is never called on the root.
+    fn node_byte(&self, node: Self::Node) -> u8;
+
+    /// Returns true iff the path from the root to `node` is a term of the trie.
+    fn is_terminal(&self, node: Self::Node) -> bool;
+}
+
+/// Iterator over the terminal nodes of a [Trie](./trait.Trie.html) accepted
+/// by a [DFA](./struct.DFA.html), together with their distance.
+///
+/// See [DFA::iter_accepted_in_trie(...)](./struct.DFA.html#method.iter_accepted_in_trie).
+pub struct AcceptedIter<'t, 'd, T: Trie> {
+    trie: &'t T,
+    dfa: &'d DFA,
+    stack: Vec<(T::Node, u32)>,
+}
+
+impl<'t, 'd, T: Trie> AcceptedIter<'t, 'd, T> {
+    pub(crate) fn new(dfa: &'d DFA, trie: &'t T) -> AcceptedIter<'t, 'd, T> {
+        AcceptedIter {
+            trie,
+            dfa,
+            stack: vec![(trie.get_root(), dfa.initial_state())],
+        }
+    }
+}
+
+impl<'t, 'd, T: Trie> Iterator for AcceptedIter<'t, 'd, T> {
+    type Item = (T::Node, Distance);
+
+    fn next(&mut self) -> Option<(T::Node, Distance)> {
+        while let Some((node, state)) = self.stack.pop() {
+            for child in self.trie.children(node) {
+                let child_state = self.dfa.transition(state, self.trie.node_byte(child));
+                if child_state != SINK_STATE {
+                    self.stack.push((child, child_state));
+                }
+            }
+            if self.trie.is_terminal(node) {
+                let distance = self.dfa.distance(state);
+                if let Distance::Exact(_) = distance {
+                    return Some((node, distance));
+                }
+            }
+        }
+        None
+    }
+}